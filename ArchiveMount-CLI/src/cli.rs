@@ -4,11 +4,16 @@ use std::io::{BufRead, BufReader};
 use std::os::windows::process::CommandExt;
 use std::path::{Path, PathBuf};
 use std::process::{Command, Stdio};
-use std::str::FromStr;
+
+use winapi::shared::minwindef::{BOOL, DWORD, TRUE};
+use winapi::um::consoleapi::SetConsoleCtrlHandler;
+
+use ArchiveMount::config::MountConfig;
+use ArchiveMount::mountOptions::MountOptions;
+use ArchiveMount::utils::console::{ConsoleEvent, ConsoleType, EXIT_ERROR, OutputFormat, setExitCode, setOutputFormat, writeConsole, writeEvent};
 
 use crate::{ARCHIVE_MOUNT_PATH, TEMP_PATH, writeEmbedFile};
-use crate::utils::console::{ConsoleType, writeConsole};
-use crate::utils::util::{installDokanDriver, isInstallDokan, registerFileMenu, String_utils, uninstallDokanDriver, unregisterFileMenu};
+use crate::utils::util::{installDokanDriver, isInstallDokan, registerFileMenu, uninstallDokanDriver, unregisterFileMenu};
 
 #[derive(Parser, Debug)]
 #[clap(version)]
@@ -16,6 +21,9 @@ use crate::utils::util::{installDokanDriver, isInstallDokan, registerFileMenu, S
 struct Cli {
     #[clap(subcommand)]
     command: Commands,
+    /// Output format(json: one event per line)
+    #[clap(long, global = true, arg_enum, default_value = "text")]
+    output: OutputFormat,
 }
 
 // 此处的 mount、unmount 命令仅提供帮助用途，实际参数将转发至 ArchiveMount.exe
@@ -30,55 +38,137 @@ enum Commands {
     /// uninstall the Archive Mount driver
     uninstall {},
     /// Mount compressed package
-    mount {
+    mount(MountOptions),
+    /// Mount all archives listed in a manifest(blocks until unmount-all or Ctrl-C)
+    mountAll {
+        /// Manifest path(TOML, one [[mount]] table per archive)
+        manifestPath: PathBuf,
+        /// Mount config file(default ArchiveMount.toml next to the program, then %APPDATA%\ArchiveMount\config.toml)
+        #[clap(long)]
+        config: Option<PathBuf>,
+    },
+    /// Unmount all archives listed in a manifest
+    unmountAll {
+        /// Manifest path
+        manifestPath: PathBuf,
+    },
+    /// Run a program from an archive, unmount after it exits(exit code of the program)
+    #[clap(trailing_var_arg = true)]
+    run {
         /// Archive path
         archivePath: PathBuf,
-        /// Mount path
-        mountPath: PathBuf,
-        /// Temporary path(default temporary directory)
-        tempPath: Option<PathBuf>,
+        /// Program path in the archive
+        program: PathBuf,
+        /// Program arguments
+        #[clap(multiple_values = true, allow_hyphen_values = true)]
+        args: Vec<String>,
         /// Archive password
         #[clap(short, long)]
         password: Option<String>,
-        /// Threads(0: auto)
-        #[clap(short, long)]
-        #[clap(default_value_t = 0_u16)]
-        threadCount: u16,
-        /// Cache size(unit: MB)
-        #[clap(short, long)]
-        #[clap(default_value_t = 4096)]
-        cacheSize: u64,
-        /// read only mount
+        /// Wait for child processes started by the program
         #[clap(short, long)]
-        readOnly: bool,
-        /// nest mount points
-        #[clap(short, long)]
-        nest: bool,
-        /// After mount open explorer
+        waitChildren: bool,
+        /// Mount config file(default ArchiveMount.toml next to the program, then %APPDATA%\ArchiveMount\config.toml)
+        #[clap(long)]
+        config: Option<PathBuf>,
+        /// Profile in the mount config
+        #[clap(long)]
+        profile: Option<String>,
+    },
+    /// Unmount compressed package
+    unmount {
+        /// mount path
+        mountPath: PathBuf,
+        /// Commit changes to the archive before unmount
         #[clap(short, long)]
-        open: bool,
-        /// Mount volume name
+        commit: bool,
+        /// Archive password(used when committing)
         #[clap(short, long)]
-        #[clap(default_value_t = String::from("ArchiveMount"))]
-        volumeName: String,
-        /// Debug mode
+        password: Option<String>,
+    },
+    /// Commit changes of a mounted archive to the archive
+    commit {
+        /// mount path
+        mountPath: PathBuf,
+        /// Archive password
         #[clap(short, long)]
-        debug: bool,
+        password: Option<String>,
     },
-    /// Unmount compressed package
-    unmount {
+    /// Manage snapshots of an overlay
+    snapshot {
+        #[clap(subcommand)]
+        command: SnapshotCommands,
+    },
+    /// List active mounts
+    list {
+        /// Output as JSON
+        #[clap(long)]
+        json: bool,
+    },
+    /// Mount manager hosting multiple mounts
+    manager {
+        #[clap(subcommand)]
+        command: ManagerCommands,
+    },
+    /// Export changes of a mounted archive as a patch archive
+    exportChanges {
         /// mount path
         mountPath: PathBuf,
+        /// Patch archive path(format by extension)
+        patchPath: PathBuf,
+        /// Patch archive password
+        #[clap(short, long)]
+        password: Option<String>,
+    },
+    /// Remove caches, mount points and mount records left by exited processes
+    cleanup {},
+}
+
+#[derive(Subcommand, Debug)]
+enum SnapshotCommands {
+    /// Snapshot the current overlay state
+    create {
+        /// Mount path or overlay directory
+        path: PathBuf,
+        /// Snapshot name
+        name: String,
+    },
+    /// List snapshots
+    list {
+        /// Mount path or overlay directory
+        path: PathBuf,
+    },
+    /// Roll the overlay back to a snapshot(pristine state if no snapshot is specified)
+    rollback {
+        /// Mount path or overlay directory
+        path: PathBuf,
+        /// Snapshot name
+        name: Option<String>,
     },
 }
 
+#[derive(Subcommand, Debug)]
+enum ManagerCommands {
+    /// Start the manager(blocks until stopped)
+    start {},
+    /// Stop the manager and unmount all archives it hosts
+    stop {},
+    /// Show the archives mounted by the manager
+    status {},
+    /// Show statistics of a mounted archive
+    stats {
+        /// mount path
+        mountPath: PathBuf,
+    },
+}
 
 pub fn cli() {
     let cli: Cli = Cli::parse();
+    setOutputFormat(cli.output);
 
     // 配置程序运行环境
-    let args: Vec<String> = env::args().skip(1).collect();
-    if args.get(0).unwrap_or(&"".to_string()) == "mount" || args.get(0).unwrap_or(&"".to_string()) == "unmount" && !ARCHIVE_MOUNT_PATH.exists() {
+    let isCoreCommand = !matches!(cli.command, Commands::install { .. } | Commands::uninstall {});
+    if matches!(cli.command, Commands::mount(_) | Commands::mountAll { .. } | Commands::run { .. }) || isCoreCommand && !ARCHIVE_MOUNT_PATH.exists() {
         writeEmbedFile("dokan1.dll", &*TEMP_PATH.join("dokan1.dll")).ok();
         writeEmbedFile(ARCHIVE_MOUNT_PATH.file_name().unwrap().to_str().unwrap(), &**ARCHIVE_MOUNT_PATH).ok();
     }
@@ -95,7 +185,7 @@ pub fn cli() {
             writeConsole(ConsoleType::Info, "install driver");
             let result = installDokanDriver();
             if result.unwrap_or(false) == false {
-                writeConsole(ConsoleType::Error, "Driver installation failed");
+                writeConsole(ConsoleType::Err, "Driver installation failed");
                 return;
             }
             // 注册右键菜单
@@ -111,42 +201,87 @@ pub fn cli() {
         Commands::uninstall {} => {
             let result = isInstallDokan();
             if result.unwrap_or(false) == false {
-                writeConsole(ConsoleType::Error, "driver not installed");
+                writeConsole(ConsoleType::Err, "driver not installed");
                 return;
             }
             writeConsole(ConsoleType::Info, "uninstall driver");
             let result = uninstallDokanDriver();
             if result.unwrap_or(false) == false {
-                writeConsole(ConsoleType::Error, "Driver uninstall failed");
+                writeConsole(ConsoleType::Err, "Driver uninstall failed");
                 return;
             }
             let _ = unregisterFileMenu();
             writeConsole(ConsoleType::Success, "Driver uninstall successfully");
         }
-        Commands::mount { archivePath: _, mountPath: _, tempPath: _, password: _, threadCount: _, cacheSize: _, nest: _, open: _, volumeName: _, debug: _, readOnly: _ } => {
-            let args: Vec<String> = env::args().skip(1).collect();
-            let stdout = Command::new(&*ARCHIVE_MOUNT_PATH).creation_flags(0x08000000)
-                .args(args)
+        Commands::mount(MountOptions { config, .. }) => {
+            let mut coreArgs = getCoreArgs();
+            addDefaultConfig(&mut coreArgs, config);
+            let mut child = Command::new(&*ARCHIVE_MOUNT_PATH).creation_flags(0x08000000)
+                .args(coreArgs)
                 .stdout(Stdio::piped())
-                .spawn().unwrap().stdout.unwrap();
+                .spawn().unwrap();
 
-            let reader = BufReader::new(stdout).lines().filter_map(|line| line.ok());
-            for content in reader {
-                let printType = ConsoleType::from_str(&*content.get_string_center("  ", "      ").unwrap().trim().to_string()).unwrap();
-                let printMessage = content.get_string_right("      ").unwrap().trim().to_string();
-                writeConsole(printType.clone(), &printMessage);
-                if printType == ConsoleType::Success || printType == ConsoleType::Error {
+            // 挂载完成或出错后返回(挂载进程继续运行)
+            let reader = BufReader::new(child.stdout.take().unwrap()).lines().filter_map(|line| line.ok());
+            for content in reader.filter(|line| !line.trim().is_empty()) {
+                let event = ConsoleEvent::parse(&content);
+                let finished = matches!(event, ConsoleEvent::mounted { .. } | ConsoleEvent::error { .. });
+                writeEvent(event);
+                if finished {
                     return;
                 }
             }
+            setExitCode(child.wait().ok().and_then(|status| status.code()).unwrap_or(EXIT_ERROR));
         }
-        Commands::unmount { mountPath: _ } => {
-            let args: Vec<String> = env::args().skip(1).collect();
-            let output = Command::new(&*ARCHIVE_MOUNT_PATH).creation_flags(0x08000000).args(args).output().unwrap();
-            let content = String::from_utf8_lossy(&output.stdout).to_string();
-            let printType = content.get_string_center("  ", "      ").unwrap().trim().to_string();
-            let printMessage = content.get_string_right("      ").unwrap().trim().to_string();
-            writeConsole(ConsoleType::from_str(&printType).unwrap(), &printMessage);
+        Commands::list { .. } | Commands::mountAll { .. } | Commands::run { .. } | Commands::manager { command: ManagerCommands::start {} } => {
+            // 挂载列表(表格/JSON)原样输出，批量挂载、运行程序及管理进程常驻运行(Ctrl-C 卸载)，均直接使用当前控制台输出
+            let mut args: Vec<String> = env::args().skip(1).collect();
+            if let Commands::mountAll { config, .. } | Commands::run { config, .. } = &cli.command {
+                addDefaultConfig(&mut args, config);
+            }
+            // Ctrl-C 由 ArchiveMount.exe 处理(卸载后退出)，当前进程等待其退出
+            unsafe { SetConsoleCtrlHandler(Some(ignoreCtrlHandler), TRUE) };
+            let status = Command::new(&*ARCHIVE_MOUNT_PATH).args(args).status();
+            setExitCode(status.ok().and_then(|status| status.code()).unwrap_or(EXIT_ERROR));
+        }
+        Commands::unmount { .. } | Commands::unmountAll { .. } | Commands::commit { .. } | Commands::snapshot { .. } | Commands::manager { .. } | Commands::exportChanges { .. } | Commands::cleanup {} => {
+            let output = Command::new(&*ARCHIVE_MOUNT_PATH).creation_flags(0x08000000).args(getCoreArgs()).output().unwrap();
+            // 提交、快照列表等会输出多行信息
+            for content in String::from_utf8_lossy(&output.stdout).lines().filter(|line| !line.trim().is_empty()) {
+                writeEvent(ConsoleEvent::parse(content));
+            }
+            setExitCode(output.status.code().unwrap_or(EXIT_ERROR));
+        }
+    }
+}
+
+/// 转发至 ArchiveMount.exe 的参数(始终以 JSON 事件输出，由当前程序按输出格式显示)
+fn getCoreArgs() -> Vec<String> {
+    let mut coreArgs = vec!["--output".to_string(), "json".to_string()];
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        if arg == "--output" {
+            args.next();
+        } else if !arg.starts_with("--output=") {
+            coreArgs.push(arg);
         }
     }
+    coreArgs
+}
+
+/// 未指定配置文件时转发默认配置文件(ArchiveMount.exe 运行于临时目录，需按当前程序的位置查找)
+/// 参数1: 转发的参数，配置文件插入在子命令之后(run 的程序参数之前)
+fn addDefaultConfig(args: &mut Vec<String>, config: &Option<PathBuf>) {
+    let configPath = match (config, MountConfig::findDefaultPath()) {
+        (None, Some(configPath)) => configPath,
+        _ => return,
+    };
+    if let Some(index) = args.iter().position(|arg| matches!(arg.as_str(), "mount" | "mount-all" | "run")) {
+        args.splice(index + 1..index + 1, ["--config".to_string(), configPath.to_str().unwrap().to_string()]);
+    }
+}
+
+/// 忽略控制台事件(由子进程处理)
+unsafe extern "system" fn ignoreCtrlHandler(_ctrlType: DWORD) -> BOOL {
+    TRUE
 }
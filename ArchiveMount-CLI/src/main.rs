@@ -8,8 +8,9 @@
 extern crate lazy_static;
 
 use rust_embed::RustEmbed;
-use std::{env, fs};
+use std::{env, fs, process};
 use std::path::PathBuf;
+use ArchiveMount::utils::console::getExitCode;
 use utils::util::writeEmbedFile;
 
 mod cli;
@@ -49,4 +50,5 @@ fn main() {
     cli::cli();
     // 删除临时目录
     let _ = fs::remove_dir_all(&*TEMP_PATH);
+    process::exit(getExitCode());
 }
@@ -1,2 +1 @@
-pub mod console;
 pub mod util;
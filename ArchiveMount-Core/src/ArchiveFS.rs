@@ -1,20 +1,31 @@
+use std::collections::HashSet;
 use std::fs;
 use std::fs::OpenOptions;
 use std::os::windows::fs::FileExt;
 use std::path::{Path, PathBuf};
 use std::process::Command;
-use std::sync::Mutex;
+use std::sync::{Arc, Mutex, RwLock};
+use std::thread;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
 use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
+use clap::ArgEnum;
+
 use dokan::{CreateFileInfo, DiskSpaceInfo, DOKAN_IO_SECURITY_CONTEXT, Drive, FileInfo, FileSystemHandler, FillDataError, FindData, MountError, MountFlags, OperationError, OperationInfo, VolumeInfo};
 use lru::LruCache;
+use serde::{Deserialize, Serialize};
 use widestring::{U16CStr, U16CString};
-use winapi::shared::ntstatus::{STATUS_ACCESS_DENIED, STATUS_INVALID_DEVICE_REQUEST, STATUS_NDIS_FILE_NOT_FOUND, STATUS_OBJECT_NAME_NOT_FOUND};
+use winapi::shared::ntstatus::{STATUS_ACCESS_DENIED, STATUS_DIRECTORY_NOT_EMPTY, STATUS_DISK_FULL, STATUS_FILE_INVALID, STATUS_INVALID_DEVICE_REQUEST, STATUS_INVALID_PARAMETER, STATUS_NDIS_FILE_NOT_FOUND, STATUS_OBJECT_NAME_COLLISION, STATUS_OBJECT_NAME_NOT_FOUND, STATUS_OBJECT_PATH_NOT_FOUND};
 use winapi::um::winnt::{FILE_CASE_PRESERVED_NAMES, FILE_PERSISTENT_ACLS, FILE_READ_ONLY_VOLUME, FILE_UNICODE_ON_DISK, FILE_VOLUME_IS_COMPRESSED};
 
+use crate::archiveLayer::{ArchiveDirectory, ArchiveLayer, listNestedLayers, mergeLayers};
+use crate::entryFilter::EntryFilter;
+use crate::overlay::{normalizePath, Overlay};
+use crate::registry::{isCommitRequested, MountLock};
 use crate::sevenZip::{ArchiveFileInfo, sevenZip};
-use crate::utils::console::{ConsoleType, writeConsole};
-use crate::utils::util::{removeVirtualDrive, StringToSystemTime};
+use crate::utils::console::{ConsoleEvent, ConsoleType, emitEvent, EventHandler};
+use crate::utils::util::{getUnixTimestamp, preallocateFile, removeVirtualDrive, StringToSystemTime};
+use crate::writeRule::{PathPattern, WritePolicy, WriteRules};
 
 const FILE_ATTRIBUTES_ARCHIVE: u32 = 32;
 const FILE_ATTRIBUTES_DIRECTORY: u32 = 16;
@@ -26,9 +37,9 @@ const FILE_ATTRIBUTES_TEMPORARY: u32 = 256;
 /// 替换文件，文件不存在时创建文件
 const FILE_SUPERSEDE: u32 = 0;
 /// 打开文件，文件不存在时返回错误
-const FILE_OPEN: u32 = 1;
+pub(crate) const FILE_OPEN: u32 = 1;
 /// 创建文件，文件存在时返回错误
-const FILE_CREATE: u32 = 2;
+pub(crate) const FILE_CREATE: u32 = 2;
 /// 打开文件，文件不存在时创建文件
 const FILE_OPEN_IF: u32 = 3;
 /// 打开文件并覆盖，文件不存在时返回错误
@@ -36,22 +47,62 @@ const FILE_OVERWRITE: u32 = 4;
 /// 打开文件并覆盖，文件不存在时创建文件
 const FILE_OVERWRITE_IF: u32 = 5;
 
+/// 检测压缩包变化的间隔
+const ARCHIVE_WATCH_INTERVAL: Duration = Duration::from_secs(2);
+
+/// 压缩包在磁盘上变化(如被新版本覆盖)时的处理方式
+#[derive(ArgEnum, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ArchiveChangeMode {
+    /// 重新读取文件列表，清除已变化文件的缓存
+    reload,
+    /// 拒绝读取压缩包(需重新挂载)
+    refuse,
+    /// 不检测
+    ignore,
+}
+
+/// 挂载运行统计
+#[derive(Debug, Default)]
+pub struct MountStats {
+    /// 是否已挂载
+    pub mounted: AtomicBool,
+    /// 打开的句柄数
+    pub openHandles: AtomicUsize,
+    /// 最后活动时间(UNIX时间戳，单位: 秒)
+    pub lastActivity: AtomicU64,
+    /// 读取字节数
+    pub readBytes: AtomicU64,
+    /// 写入字节数
+    pub writeBytes: AtomicU64,
+}
+
+impl MountStats {
+    /// 更新最后活动时间
+    pub fn touch(&self) {
+        self.lastActivity.store(getUnixTimestamp(), Ordering::Relaxed);
+    }
+}
+
 #[derive(Debug)]
 pub struct ArchiveFS {
     /// SevenZIP程序类
     sevenZip: sevenZip,
-    /// 压缩包路径
-    archivePath: PathBuf,
-    /// 压缩包密码
-    password: Option<String>,
-    /// 临时释放路径
-    extractPath: PathBuf,
+    /// 压缩包层(第一层为基础压缩包，其后为补丁压缩包，压缩包变化时重新读取)
+    layers: RwLock<Arc<Vec<ArchiveLayer>>>,
+    /// 可写覆盖层(未匹配写入规则的文件)
+    overlay: Overlay,
+    /// 写入规则
+    writeRules: WriteRules,
+    /// 配置目录覆盖层(persist 规则匹配的文件)
+    profileOverlay: Option<Overlay>,
+    /// 丢弃覆盖层(discard 规则匹配的文件，卸载后删除)
+    discardOverlay: Option<Overlay>,
     /// 缓存大小(单位: MB)
     cacheSize: u64,
     /// 是否只读挂载
     readOnly: bool,
-    /// 压缩包文件信息
-    archiveFileInfoList: Vec<ArchiveFileInfo>,
+    /// 压缩包文件信息(合并所有压缩包层)
+    archiveFileInfoList: RwLock<Arc<Vec<ArchiveFileInfo>>>,
     /// 缓存信息
     cacheInfoList: Mutex<LruCache<ArchiveFileInfo, PathBuf>>,
     /// 挂载后是否打开
@@ -60,24 +111,163 @@ pub struct ArchiveFS {
     volumeName: String,
     /// 是否为调试模式
     isDebugMode: bool,
+    /// 运行统计
+    stats: MountStats,
+    /// 事件回调(未设置时输出至控制台)
+    eventHandler: Option<EventHandler>,
+    /// 压缩包变化时的处理方式
+    archiveChangeMode: ArchiveChangeMode,
+    /// 压缩包已变化(refuse 模式下拒绝读取压缩包)
+    archiveChanged: AtomicBool,
+    /// 卸载时是否提交覆盖层的修改至压缩包
+    commitOnUnmount: bool,
+    /// 缓存目录锁(文件系统释放时解锁)
+    cacheLock: Option<MountLock>,
+    /// 压缩包目录(目录挂载时设置)
+    archiveDirectory: Option<ArchiveDirectory>,
+    /// 修改压缩包层时持有(重新读取、首次读取、加入新增的压缩包)
+    layerLock: Mutex<()>,
+    /// 展开嵌套压缩包的最大层数(0为不展开)
+    nestedDepth: u32,
+    /// 挂载根目录(压缩包中的目录，为空时挂载整个压缩包)
+    rootPath: String,
+    /// 条目过滤规则(隐藏匹配的条目)
+    entryFilter: EntryFilter,
 }
 
 impl ArchiveFS {
-    pub(crate) fn new(archivePath: &Path, extractPath: &Path, password: Option<&str>, cacheSize: u64, readOnly: bool, archiveFileInfoList: Vec<ArchiveFileInfo>, open: bool, volumeName: &str, isDebugMode: bool) -> ArchiveFS {
-        fs::create_dir_all(extractPath).ok();
+    pub(crate) fn new(sevenZip: sevenZip, layers: Vec<ArchiveLayer>, overlay: Overlay, cacheSize: u64, readOnly: bool, open: bool, volumeName: &str, isDebugMode: bool) -> ArchiveFS {
+        for layer in layers.iter() {
+            fs::create_dir_all(&layer.extractPath).ok();
+        }
+        let entryFilter = EntryFilter::default();
+        let archiveFileInfoList = mergeFileList(&layers, &entryFilter);
         ArchiveFS {
-            sevenZip: sevenZip::new().unwrap(),
-            archivePath: (*archivePath.to_path_buf()).to_owned(),
-            password: password.map(|password| password.to_string()),
-            extractPath: (*extractPath.to_path_buf()).to_owned(),
+            sevenZip,
+            layers: RwLock::new(Arc::new(layers)),
+            overlay,
+            writeRules: WriteRules::default(),
+            profileOverlay: None,
+            discardOverlay: None,
             cacheSize,
             readOnly,
-            archiveFileInfoList,
+            archiveFileInfoList: RwLock::new(Arc::new(archiveFileInfoList)),
             cacheInfoList: Mutex::new(LruCache::unbounded()),
             open,
             volumeName: volumeName.to_string(),
             isDebugMode,
+            stats: MountStats::default(),
+            eventHandler: None,
+            archiveChangeMode: ArchiveChangeMode::reload,
+            archiveChanged: AtomicBool::new(false),
+            commitOnUnmount: false,
+            cacheLock: None,
+            archiveDirectory: None,
+            layerLock: Mutex::new(()),
+            nestedDepth: 0,
+            rootPath: String::new(),
+            entryFilter,
+        }
+    }
+
+    /// 设置事件回调
+    pub(crate) fn setEventHandler(&mut self, eventHandler: Option<EventHandler>) {
+        self.eventHandler = eventHandler;
+    }
+
+    /// 输出事件(设置事件回调时由回调处理)
+    fn emit(&self, consoleType: ConsoleType, message: &str) {
+        emitEvent(self.eventHandler.as_ref(), ConsoleEvent::new(consoleType, message));
+    }
+
+    /// 设置压缩包变化时的处理方式
+    pub(crate) fn setArchiveChangeMode(&mut self, archiveChangeMode: ArchiveChangeMode) {
+        self.archiveChangeMode = archiveChangeMode;
+    }
+
+    /// 设置卸载时是否提交覆盖层的修改
+    pub(crate) fn setCommitOnUnmount(&mut self, commitOnUnmount: bool) {
+        self.commitOnUnmount = commitOnUnmount;
+    }
+
+    /// 设置缓存目录锁
+    pub(crate) fn setCacheLock(&mut self, cacheLock: MountLock) {
+        self.cacheLock = Some(cacheLock);
+    }
+
+    /// 设置压缩包目录(目录挂载)
+    pub(crate) fn setArchiveDirectory(&mut self, archiveDirectory: ArchiveDirectory) {
+        self.archiveDirectory = Some(archiveDirectory);
+    }
+
+    /// 设置展开嵌套压缩包的最大层数
+    pub(crate) fn setNestedDepth(&mut self, nestedDepth: u32) {
+        self.nestedDepth = nestedDepth;
+    }
+
+    /// 设置条目过滤规则(重新过滤文件列表)
+    pub(crate) fn setEntryFilter(&mut self, mut entryFilter: EntryFilter) {
+        entryFilter.setRootPath(&self.rootPath);
+        self.entryFilter = entryFilter;
+        let archiveFileInfoList = mergeFileList(&self.layers(), &self.entryFilter);
+        *self.archiveFileInfoList.get_mut().unwrap() = Arc::new(archiveFileInfoList);
+    }
+
+    /// 设置挂载根目录(压缩包中的目录作为挂载的根目录)
+    /// 参数1: 根目录路径
+    /// 参数2: 去除的顶层目录层数(每层仅包含一个目录)
+    pub(crate) fn setRootPath(&mut self, rootPath: Option<&str>, stripComponents: u32) -> anyhow::Result<()> {
+        let mut rootPath = rootPath.map(normalizePath).unwrap_or_default();
+        if !rootPath.is_empty() {
+            match self.findArchiveFile(&rootPath) {
+                Some(item) if item.is_dir => rootPath = item.Path,
+                _ => anyhow::bail!("The root path does not exist in the archive: {}", rootPath),
+            }
+        }
+        for level in 1..=stripComponents {
+            self.indexLayer(&rootPath, true);
+            let parentPath = rootPath.to_lowercase();
+            let fileList = self.fileList();
+            let childList: Vec<&ArchiveFileInfo> = fileList.iter().filter(|item| getParentPath(&item.Path).to_lowercase() == parentPath && !self.isDeleted(&item.Path)).collect();
+            match childList.as_slice() {
+                [item] if item.is_dir => rootPath = item.Path.clone(),
+                _ => anyhow::bail!("Strip {} components failed, the archive does not contain a single directory at level {}", stripComponents, level),
+            }
         }
+        if !rootPath.is_empty() {
+            self.emit(ConsoleType::Info, &*format!("Mounting directory in the archive as root: {}", rootPath));
+        }
+        self.rootPath = rootPath;
+        // 过滤规则匹配挂载中的路径
+        self.setEntryFilter(self.entryFilter.clone());
+        Ok(())
+    }
+
+    /// 挂载中的路径转换为压缩包中的路径(加上挂载根目录)
+    pub(crate) fn getRelativePath(&self, file_name: &str) -> String {
+        let path = file_name.trim_start_matches('\\');
+        if self.rootPath.is_empty() {
+            path.to_string()
+        } else if path.is_empty() {
+            self.rootPath.clone()
+        } else {
+            format!("{}\\{}", self.rootPath, path)
+        }
+    }
+
+    /// 压缩包中的路径转换为挂载中的路径(不在挂载根目录中时返回 None)
+    pub(crate) fn getMountedPath<'p>(&self, relativePath: &'p str) -> Option<&'p str> {
+        stripRootPath(&self.rootPath, relativePath)
+    }
+
+    /// 设置写入规则
+    /// 参数1: 写入规则
+    /// 参数2: 配置目录覆盖层(persist 规则)
+    /// 参数3: 丢弃覆盖层(discard 规则)
+    pub(crate) fn setWriteRules(&mut self, writeRules: WriteRules, profileOverlay: Option<Overlay>, discardOverlay: Option<Overlay>) {
+        self.writeRules = writeRules;
+        self.profileOverlay = profileOverlay;
+        self.discardOverlay = discardOverlay;
     }
 
     /// 挂载
@@ -108,27 +298,521 @@ impl ArchiveFS {
         // 由dokan卸载钩子处理
         dokan::unmount(&convert_str(mountPath.to_str().unwrap()))
     }
-}
 
-#[derive(Debug, Clone)]
-pub struct SevenContext {
-    FileInfo: ArchiveFileInfo,
-    localFilePath: PathBuf,
-}
+    /// 运行统计
+    pub fn stats(&self) -> &MountStats {
+        &self.stats
+    }
 
-impl<'a, 'b: 'a> FileSystemHandler<'a, 'b> for ArchiveFS {
-    type Context = Option<SevenContext>;
+    /// 缓存占用大小(单位: 字节)
+    pub fn cacheUsage(&self) -> u64 {
+        self.cacheInfoList.lock().unwrap().iter().map(|item| item.0.Size).sum()
+    }
 
-    /// 创建文件对象时调用
-    fn create_file(&'b self, file_name: &U16CStr, _security_context: &DOKAN_IO_SECURITY_CONTEXT, _desired_access: u32, _file_attributes: u32, _share_access: u32, create_disposition: u32, _create_options: u32, info: &mut OperationInfo<'a, 'b, Self>) -> Result<CreateFileInfo<Self::Context>, OperationError> {
+    /// 预先解压匹配的文件至缓存(超出缓存大小时停止)，返回解压的文件数
+    pub fn prefetch(&self, patternList: &[PathPattern]) -> usize {
+        let mut count = 0;
+        let mut totalSize = 0;
+        for fileInfo in self.fileList().iter() {
+            let mountedPath = match self.getMountedPath(&fileInfo.Path) {
+                Some(mountedPath) => mountedPath,
+                None => continue,
+            };
+            if fileInfo.is_dir || self.isDeleted(&fileInfo.Path) || !patternList.iter().any(|pattern| pattern.isMatch(mountedPath)) {
+                continue;
+            }
+            totalSize += fileInfo.Size;
+            if totalSize / 1024 / 1024 >= self.cacheSize {
+                self.emit(ConsoleType::Warning, "Prefetch stopped, the cache size is exceeded");
+                break;
+            }
+            if self.extractFile(fileInfo, &self.getLocalFilePath(fileInfo)).is_ok() {
+                count += 1;
+            }
+        }
+        self.emit(ConsoleType::Info, &*format!("Prefetched {} files", count));
+        count
+    }
+
+    /// 是否空闲(无打开的句柄，且超过时长无读写)
+    pub fn isIdle(&self, idleTimeout: Duration) -> bool {
+        self.stats.openHandles.load(Ordering::SeqCst) == 0
+            && getUnixTimestamp().saturating_sub(self.stats.lastActivity.load(Ordering::Relaxed)) >= idleTimeout.as_secs()
+    }
+
+    /// 空闲超过时长后卸载(阻塞至卸载)
+    pub(crate) fn watchIdle(&self, mountPath: &Path, idleTimeout: Duration) {
+        while self.stats.mounted.load(Ordering::SeqCst) {
+            thread::sleep(Duration::from_secs(1));
+            if self.isIdle(idleTimeout) {
+                self.emit(ConsoleType::Info, &*format!("The volume has been idle for {}s, unmounting", idleTimeout.as_secs()));
+                ArchiveFS::unmount(mountPath);
+                break;
+            }
+        }
+    }
+
+    /// 监视压缩包文件变化及压缩包目录中新增的压缩包(阻塞至卸载)
+    pub(crate) fn watchArchive(&self) {
+        if self.archiveChangeMode == ArchiveChangeMode::ignore && self.archiveDirectory.is_none() {
+            return;
+        }
+        // 嵌套压缩包位于缓存目录中，随外层压缩包重新读取
+        let getStateList = || self.layers().iter().map(|layer| if layer.source.is_some() { None } else { getArchiveState(&layer.volumeList) }).collect::<Vec<_>>();
+        let mut stateList = getStateList();
+        // 待确认的状态(复制压缩包时大小、修改时间持续变化，两次检测一致后再处理)
+        let mut pendingList = stateList.clone();
+        while self.stats.mounted.load(Ordering::SeqCst) {
+            thread::sleep(ARCHIVE_WATCH_INTERVAL);
+            self.discoverArchives();
+            if self.archiveChangeMode == ArchiveChangeMode::ignore {
+                continue;
+            }
+            // 新加入的压缩包(只会追加)以当前状态为初始状态
+            let currentList = getStateList();
+            stateList.extend_from_slice(&currentList[stateList.len()..]);
+            pendingList.extend_from_slice(&currentList[pendingList.len()..]);
+            let changedList = match getChangedLayers(&stateList, &pendingList, &currentList) {
+                Some(changedList) => changedList,
+                None => {
+                    pendingList = currentList;
+                    continue;
+                }
+            };
+            if let Err(err) = self.onArchiveChanged(&changedList) {
+                self.emit(ConsoleType::Warning, &*format!("The archive has changed on disk but could not be read: {}", err));
+            }
+            stateList = currentList;
+        }
+    }
+
+    /// 压缩包变化: 重新读取文件列表并清除已变化文件的缓存，或拒绝读取
+    pub(crate) fn onArchiveChanged(&self, changedList: &[usize]) -> anyhow::Result<()> {
+        let oldLayers = self.layers();
+        let changedPath = changedList.iter().map(|&index| oldLayers[index].archivePath.display().to_string()).collect::<Vec<String>>().join(", ");
+        if self.archiveChangeMode == ArchiveChangeMode::refuse {
+            self.archiveChanged.store(true, Ordering::SeqCst);
+            self.emit(ConsoleType::Warning, &*format!("The archive has changed on disk, reading is refused until it is remounted: {}", changedPath));
+            return Ok(());
+        }
+
+        let _layerGuard = self.layerLock.lock().unwrap();
+        let oldLayers = self.layers();
+        let mut layers: Vec<ArchiveLayer> = Vec::new();
+        // 已重新读取的压缩包层(其中的嵌套压缩包需重新读取)
+        let mut resetList = changedList.to_vec();
+        for (index, layer) in oldLayers.iter().enumerate() {
+            if let Some(source) = layer.source.as_ref().filter(|source| resetList.contains(&source.layer)) {
+                // 更新外层条目信息，嵌套压缩包在下次访问时重新解压并读取
+                let sourceLayer = &layers[source.layer];
+                let sourceInfo = sourceLayer.archiveFileInfoList.iter().find(|item| sourceLayer.getMountedPath(&item.Path).to_lowercase() == source.fileInfo.Path.to_lowercase());
+                let mut nestedLayer = layer.unindex();
+                if let (Some(nestedSource), Some(sourceInfo)) = (nestedLayer.source.as_mut(), sourceInfo) {
+                    nestedSource.fileInfo = ArchiveFileInfo { Path: source.fileInfo.Path.clone(), Layer: source.layer, ..sourceInfo.clone() };
+                }
+                resetList.push(index);
+                layers.push(nestedLayer);
+            } else if !changedList.contains(&index) || !layer.indexed {
+                layers.push(layer.clone());
+            } else if !layer.prefix.is_empty() && !layer.archivePath.is_file() {
+                // 目录挂载中已删除的压缩包(重新出现时再次读取)
+                layers.push(ArchiveLayer::unindexed(&layer.archivePath, layer.password.as_deref(), &layer.extractPath, &layer.prefix));
+            } else {
+                layers.push(layer.index(&self.sevenZip)?);
+            }
+        }
+        for &index in changedList.iter() {
+            let nestedLayers = listNestedLayers(&layers, index, self.nestedDepth);
+            layers.extend(nestedLayers);
+        }
+        let archiveFileInfoList = mergeFileList(&layers, &self.entryFilter);
+
+        // 清除已变化文件的缓存(大小、CRC、修改时间或所属层不同，或已不存在)
+        let mut cacheList = self.cacheInfoList.lock().unwrap();
+        let mut fileSet: HashSet<&ArchiveFileInfo> = archiveFileInfoList.iter().collect();
+        // 已解压的嵌套压缩包(显示为目录，不在文件列表中)
+        fileSet.extend(layers.iter().filter_map(|layer| layer.source.as_ref().map(|source| &source.fileInfo)));
+        let staleList: Vec<ArchiveFileInfo> = cacheList.iter().filter(|item| !fileSet.contains(item.0)).map(|item| item.0.clone()).collect();
+        for fileInfo in staleList.iter() {
+            if let Some(localFilePath) = cacheList.pop(fileInfo) {
+                fs::remove_file(localFilePath).ok();
+            }
+        }
+        let entryCount = archiveFileInfoList.len();
+        *self.layers.write().unwrap() = Arc::new(layers);
+        *self.archiveFileInfoList.write().unwrap() = Arc::new(archiveFileInfoList);
+        drop(cacheList);
+        self.emit(ConsoleType::Info, &*format!("The archive has changed on disk, reloaded {} entries and invalidated {} cached files: {}", entryCount, staleList.len(), changedPath));
+        Ok(())
+    }
+
+    /// 卸载时提交覆盖层的修改至压缩包(未启用、只读、目录挂载或无修改时跳过)，返回是否已提交
+    pub(crate) fn commitUnmountChanges(&self) -> bool {
+        if !self.commitOnUnmount || self.readOnly || self.archiveDirectory.is_some() || !self.overlay.hasChanges() {
+            return false;
+        }
+        let layers = self.layers();
+        let baseLayer = &layers[0];
+        self.emit(ConsoleType::Progress, &*format!("Committing changes to archive: {}", baseLayer.archivePath.to_str().unwrap()));
+        if let Err(err) = self.overlay.commit(&self.sevenZip, &baseLayer.archivePath, baseLayer.password.as_deref()) {
+            self.emit(ConsoleType::Err, &*format!("commit failed: {}", err));
+            return false;
+        }
+        true
+    }
+
+    /// 读取路径所在压缩包的文件列表(目录挂载及展开嵌套压缩包时，首次访问压缩包子目录时读取)
+    /// 参数2: 路径为子目录本身时是否读取(列出子目录)
+    fn indexLayer(&self, relativePath: &str, includeSelf: bool) {
+        if relativePath.is_empty() {
+            return;
+        }
+        let relativePath = relativePath.to_lowercase();
+        let isTarget = |layer: &ArchiveLayer| {
+            if layer.indexed || layer.prefix.is_empty() {
+                return false;
+            }
+            let prefix = layer.prefix.to_lowercase();
+            (includeSelf && relativePath == prefix) || relativePath.starts_with(&format!("{}\\", prefix))
+        };
+        // 读取后可能出现其中的嵌套压缩包，逐层读取
+        while self.layers().iter().any(|layer| isTarget(layer)) {
+            // 其他线程可能已完成读取
+            let _layerGuard = self.layerLock.lock().unwrap();
+            let mut layers = (*self.layers()).clone();
+            let index = match layers.iter().position(|layer| isTarget(layer)) {
+                Some(index) => index,
+                None => return,
+            };
+            let layer = &layers[index];
+            let displayPath = if layer.source.is_some() { layer.prefix.clone() } else { layer.archivePath.display().to_string() };
+            self.emit(ConsoleType::Progress, &*format!("Reading archive list: {}", displayPath));
+            // 嵌套压缩包先解压至外层的缓存目录
+            let result = match &layer.source {
+                Some(source) if self.extractFile(&source.fileInfo, &layer.archivePath).is_err() => Err(anyhow::anyhow!("Extract archive failed")),
+                _ => layer.index(&self.sevenZip),
+            };
+            layers[index] = match result {
+                Ok(layer) => layer,
+                Err(err) => {
+                    self.emit(ConsoleType::Warning, &*format!("Read archive failed: {}: {}", displayPath, err));
+                    // 读取失败时显示为空目录
+                    let mut layer = layers[index].clone();
+                    layer.indexed = true;
+                    layer
+                }
+            };
+            let nestedLayers = listNestedLayers(&layers, index, self.nestedDepth);
+            layers.extend(nestedLayers);
+            let archiveFileInfoList = mergeFileList(&layers, &self.entryFilter);
+            *self.layers.write().unwrap() = Arc::new(layers);
+            *self.archiveFileInfoList.write().unwrap() = Arc::new(archiveFileInfoList);
+        }
+    }
+
+    /// 加入压缩包目录中新增的压缩包(首次访问时读取文件列表)
+    fn discoverArchives(&self) {
+        let archiveDirectory = match &self.archiveDirectory {
+            Some(archiveDirectory) => archiveDirectory,
+            None => return,
+        };
+        if archiveDirectory.listNewLayers(&self.layers()).is_empty() {
+            return;
+        }
+        let _layerGuard = self.layerLock.lock().unwrap();
+        let mut layers = (*self.layers()).clone();
+        let newLayers = archiveDirectory.listNewLayers(&layers);
+        let nameList = newLayers.iter().map(|layer| layer.prefix.clone()).collect::<Vec<String>>().join(", ");
+        layers.extend(newLayers);
+        let archiveFileInfoList = mergeFileList(&layers, &self.entryFilter);
+        *self.layers.write().unwrap() = Arc::new(layers);
+        *self.archiveFileInfoList.write().unwrap() = Arc::new(archiveFileInfoList);
+        self.emit(ConsoleType::Info, &*format!("Found new archives: {}", nameList));
+    }
+
+    /// 缓存根目录(目录挂载时为压缩包目录的缓存目录，否则为基础压缩包的缓存目录)
+    fn getCachePath(&self) -> PathBuf {
+        match &self.archiveDirectory {
+            Some(archiveDirectory) => archiveDirectory.extractPath.clone(),
+            None => self.layers()[0].extractPath.clone(),
+        }
+    }
+
+    /// 压缩包层(快照)
+    pub(crate) fn layers(&self) -> Arc<Vec<ArchiveLayer>> {
+        self.layers.read().unwrap().clone()
+    }
+
+    /// 压缩包文件信息(快照)
+    pub(crate) fn fileList(&self) -> Arc<Vec<ArchiveFileInfo>> {
+        self.archiveFileInfoList.read().unwrap().clone()
+    }
+
+    /// 所有覆盖层
+    fn overlays(&self) -> impl Iterator<Item=&Overlay> {
+        std::iter::once(&self.overlay).chain(self.profileOverlay.iter()).chain(self.discardOverlay.iter())
+    }
+
+    /// 获取条目所属的覆盖层(由写入规则决定)
+    fn getOverlay(&self, relativePath: &str) -> &Overlay {
+        let overlay = match self.writeRules.getPolicy(self.getMountedPath(relativePath).unwrap_or(relativePath)) {
+            Some(WritePolicy::Persist) => self.profileOverlay.as_ref(),
+            Some(WritePolicy::Discard) => self.discardOverlay.as_ref(),
+            _ => None,
+        };
+        overlay.unwrap_or(&self.overlay)
+    }
+
+    /// 获取条目在所属覆盖层中的路径
+    fn getUpperFilePath(&self, relativePath: &str) -> PathBuf {
+        self.getOverlay(relativePath).getUpperFilePath(relativePath)
+    }
+
+    /// 判断条目是否已被删除(任意覆盖层记录了删除)
+    fn isDeleted(&self, relativePath: &str) -> bool {
+        self.overlays().any(|overlay| overlay.isDeleted(relativePath))
+    }
+
+    /// 检查写入规则是否允许修改条目
+    fn checkWritable(&self, relativePath: &str) -> Result<(), OperationError> {
+        if self.writeRules.getPolicy(self.getMountedPath(relativePath).unwrap_or(relativePath)) == Some(WritePolicy::Deny) {
+            if self.isDebugMode {
+                self.emit(ConsoleType::Warning, &*format!("Write denied by rule: {}", relativePath));
+            }
+            return Err(OperationError::NtStatus(STATUS_ACCESS_DENIED));
+        }
+        Ok(())
+    }
+
+    /// 查找压缩包内的条目(已删除的条目不返回)
+    fn findArchiveFile(&self, relativePath: &str) -> Option<ArchiveFileInfo> {
+        if self.isDeleted(relativePath) {
+            return None;
+        }
+        self.indexLayer(relativePath, false);
+        let relativePath = relativePath.to_lowercase();
+        self.fileList().iter().find(|item| item.Path.to_lowercase() == relativePath).cloned()
+    }
+
+    /// 判断目录是否存在(根目录、上层目录、压缩包目录)
+    fn isDirectoryExists(&self, relativePath: &str) -> bool {
+        if relativePath.is_empty() {
+            return true;
+        }
+        if !self.readOnly && self.overlays().any(|overlay| overlay.getUpperFilePath(relativePath).is_dir()) {
+            return true;
+        }
+        self.findArchiveFile(relativePath).map(|item| item.is_dir).unwrap_or(false)
+    }
+
+    /// 判断目录是否包含未删除的子项
+    fn hasChildren(&self, relativePath: &str) -> bool {
+        for overlay in self.overlays() {
+            if let Ok(mut entries) = overlay.getUpperFilePath(relativePath).read_dir() {
+                if entries.next().is_some() {
+                    return true;
+                }
+            }
+        }
+        self.indexLayer(relativePath, true);
+        let relativePath = relativePath.to_lowercase();
+        self.fileList().iter().any(|item| getParentPath(&item.Path).to_lowercase() == relativePath && !self.isDeleted(&item.Path))
+    }
+
+    /// 文件在所属压缩包层缓存目录中的路径
+    fn getLocalFilePath(&self, fileInfo: &ArchiveFileInfo) -> PathBuf {
+        let layers = self.layers();
+        let layer = &layers[fileInfo.Layer];
+        layer.extractPath.join(layer.getArchivePath(&fileInfo.Path))
+    }
+
+    /// 解压文件至缓存目录
+    pub(crate) fn extractFile(&self, fileInfo: &ArchiveFileInfo, localFilePath: &Path) -> Result<(), OperationError> {
+        // 压缩包已变化，缓存及文件列表均不可信
+        if self.archiveChanged.load(Ordering::SeqCst) {
+            if self.isDebugMode {
+                self.emit(ConsoleType::Warning, &*format!("Read refused, the archive has changed: {}", &*fileInfo.Path));
+            }
+            return Err(OperationError::NtStatus(STATUS_FILE_INVALID));
+        }
+        if localFilePath.exists() {
+            return Ok(());
+        }
+        // 嵌套压缩包中的文件: 外层条目已被清理出缓存时重新解压
+        let layers = self.layers();
+        let layer = &layers[fileInfo.Layer];
+        if let Some(source) = &layer.source {
+            self.extractFile(&source.fileInfo, &layer.archivePath)?;
+        }
+        let mut cacheList = self.cacheInfoList.lock().unwrap();
+        if let Some(source) = &layer.source {
+            // 避免清理缓存时删除外层条目
+            cacheList.get(&source.fileInfo);
+        }
+        // 自动清理缓存(循环当 缓存总大小 + 当前需要解压文件大小 >= 设置缓存大小)
+        while (cacheList.iter().map(|item| item.0.Size).sum::<u64>() + fileInfo.Size) / 1024 / 1024 >= self.cacheSize as u64 {
+            if let Some(lruInfo) = cacheList.pop_lru() {
+                if self.isDebugMode { self.emit(ConsoleType::Info, &*format!("Delete Cache: {}", &lruInfo.1.display())); }
+                fs::remove_file(&lruInfo.1).ok();
+            } else {
+                break;
+            }
+        }
+
+        // 解压文件(从文件所属的压缩包层)
+        if self.isDebugMode {
+            self.emit(ConsoleType::Progress, &*format!("Extracting file: {}\\{}", &*layer.archivePath.to_str().unwrap(), &*fileInfo.Path));
+        }
+        if !self.sevenZip.extractFilesFromPath(&*layer.archivePath, layer.password.as_deref(), layer.getArchivePath(&fileInfo.Path), &layer.extractPath).unwrap_or(false) && !localFilePath.exists() {
+            if self.isDebugMode {
+                self.emit(ConsoleType::Warning, &*format!("Extract file failed: {}\\{}", &*layer.archivePath.to_str().unwrap(), &*fileInfo.Path));
+            }
+            return Err(OperationError::NtStatus(STATUS_INVALID_DEVICE_REQUEST));
+        }
+
+        // 增加缓存信息
+        cacheList.put(fileInfo.clone(), localFilePath.to_path_buf());
+        Ok(())
+    }
+
+    /// 将文件复制到上层(修改压缩包内文件前调用)
+    fn copyUp(&self, relativePath: &str, fileInfo: &ArchiveFileInfo, localFilePath: &Path) -> Result<PathBuf, OperationError> {
+        self.checkWritable(relativePath)?;
+        let upperFilePath = self.getUpperFilePath(relativePath);
+        if upperFilePath.exists() {
+            return Ok(upperFilePath);
+        }
+        if let Some(parent) = upperFilePath.parent() {
+            fs::create_dir_all(parent).map_err(|_| OperationError::NtStatus(STATUS_ACCESS_DENIED))?;
+        }
+        if fileInfo.is_dir {
+            fs::create_dir_all(&upperFilePath).map_err(|_| OperationError::NtStatus(STATUS_ACCESS_DENIED))?;
+            return Ok(upperFilePath);
+        }
+        self.extractFile(fileInfo, localFilePath)?;
+        fs::copy(localFilePath, &upperFilePath).map_err(|_| OperationError::NtStatus(STATUS_ACCESS_DENIED))?;
+        Ok(upperFilePath)
+    }
+
+    /// 在上层创建条目(重新创建已删除的目录时隐藏其原有子项)
+    fn createUpperEntry(&self, relativePath: &str, isDir: bool) -> Result<PathBuf, OperationError> {
+        self.checkWritable(relativePath)?;
+        if self.getOverlay(relativePath).removeWhiteout(relativePath) && isDir {
+            let lowerPath = relativePath.to_lowercase();
+            for item in self.fileList().iter().filter(|item| getParentPath(&item.Path).to_lowercase() == lowerPath) {
+                self.getOverlay(&item.Path).addWhiteout(&item.Path);
+            }
+        }
+        let upperFilePath = self.getUpperFilePath(relativePath);
+        if let Some(parent) = upperFilePath.parent() {
+            fs::create_dir_all(parent).ok();
+        }
+        let result = if isDir { fs::create_dir_all(&upperFilePath) } else { fs::File::create(&upperFilePath).map(|_| ()) };
+        result.map_err(|_| OperationError::NtStatus(STATUS_ACCESS_DENIED))?;
+        Ok(upperFilePath)
+    }
+
+    /// 删除条目: 移除上层文件，压缩包中存在时记录删除
+    pub(crate) fn removeEntry(&self, relativePath: &str) {
+        let upperFilePath = self.getUpperFilePath(relativePath);
+        if upperFilePath.is_dir() {
+            fs::remove_dir_all(&upperFilePath).ok();
+        } else if upperFilePath.exists() {
+            fs::remove_file(&upperFilePath).ok();
+        }
+        if self.findArchiveFile(relativePath).is_some() {
+            self.getOverlay(relativePath).addWhiteout(relativePath);
+        }
+    }
+
+    /// 在指定位置写入文件(写入前复制到上层)，返回写入的字节数
+    /// 参数4: 是否追加写入(忽略偏移)
+    /// 参数5: 是否为分页IO(不能扩展文件大小)
+    pub(crate) fn writeFile(&self, relativePath: &str, context: &SevenContext, offset: i64, buffer: &[u8], writeToEof: bool, pagingIo: bool) -> Result<u32, OperationError> {
+        if self.readOnly || context.FileInfo.is_dir {
+            return Err(OperationError::NtStatus(STATUS_ACCESS_DENIED));
+        }
+        if offset < 0 && !writeToEof {
+            return Err(OperationError::NtStatus(STATUS_INVALID_PARAMETER));
+        }
+        let upperFilePath = self.copyUp(relativePath, &context.FileInfo, &context.localFilePath)?;
+        let file = OpenOptions::new().write(true).open(&upperFilePath).map_err(|_| OperationError::NtStatus(STATUS_ACCESS_DENIED))?;
+        let fileSize = file.metadata().map(|metadata| metadata.len()).unwrap_or(0);
+        let offset = if writeToEof { fileSize } else { offset as u64 };
+        let buffer = if pagingIo {
+            if offset >= fileSize {
+                return Ok(0);
+            }
+            &buffer[..buffer.len().min((fileSize - offset) as usize)]
+        } else {
+            buffer
+        };
+        let result = file.seek_write(buffer, offset).map_err(|_| OperationError::NtStatus(STATUS_ACCESS_DENIED))?;
+        self.stats.writeBytes.fetch_add(result as u64, Ordering::Relaxed);
+        Ok(result as u32)
+    }
+
+    /// 设置文件大小(截断或扩展，扩展部分填充 0)
+    pub(crate) fn setFileSize(&self, relativePath: &str, context: &SevenContext, size: i64) -> Result<(), OperationError> {
+        if self.readOnly || context.FileInfo.is_dir {
+            return Err(OperationError::NtStatus(STATUS_ACCESS_DENIED));
+        }
+        if size < 0 {
+            return Err(OperationError::NtStatus(STATUS_INVALID_PARAMETER));
+        }
+        let upperFilePath = self.copyUp(relativePath, &context.FileInfo, &context.localFilePath)?;
+        let file = OpenOptions::new().write(true).open(&upperFilePath).map_err(|_| OperationError::NtStatus(STATUS_ACCESS_DENIED))?;
+        file.set_len(size as u64).map_err(|_| OperationError::NtStatus(STATUS_ACCESS_DENIED))
+    }
+
+    /// 设置分配大小: 小于文件大小时截断，否则为上层文件预分配磁盘空间(不改变文件大小)
+    pub(crate) fn setAllocationSize(&self, relativePath: &str, context: &SevenContext, size: i64) -> Result<(), OperationError> {
+        if self.readOnly || context.FileInfo.is_dir {
+            return Err(OperationError::NtStatus(STATUS_ACCESS_DENIED));
+        }
+        if size < 0 {
+            return Err(OperationError::NtStatus(STATUS_INVALID_PARAMETER));
+        }
+        let upperFilePath = self.getUpperFilePath(relativePath);
+        let fileSize = upperFilePath.metadata().map(|metadata| metadata.len()).unwrap_or(context.FileInfo.Size);
+        if size as u64 >= fileSize && !upperFilePath.exists() {
+            // 压缩包中的文件在写入时才复制到上层，无需预分配
+            return Ok(());
+        }
+        let upperFilePath = self.copyUp(relativePath, &context.FileInfo, &context.localFilePath)?;
+        let file = OpenOptions::new().write(true).open(&upperFilePath).map_err(|_| OperationError::NtStatus(STATUS_ACCESS_DENIED))?;
+        if (size as u64) < fileSize {
+            return file.set_len(size as u64).map_err(|_| OperationError::NtStatus(STATUS_ACCESS_DENIED));
+        }
+        if !preallocateFile(&file, size as u64) {
+            return Err(OperationError::NtStatus(STATUS_DISK_FULL));
+        }
+        Ok(())
+    }
+
+    /// 检查目录能否删除(仅允许删除空目录)
+    pub(crate) fn checkDeleteDirectory(&self, relativePath: &str) -> Result<(), OperationError> {
+        if self.readOnly {
+            return Err(OperationError::NtStatus(STATUS_ACCESS_DENIED));
+        }
+        self.checkWritable(relativePath)?;
+        if self.hasChildren(relativePath) {
+            return Err(OperationError::NtStatus(STATUS_DIRECTORY_NOT_EMPTY));
+        }
+        Ok(())
+    }
+
+    /// 打开或创建文件
+    pub(crate) fn createFile(&self, file_name: &U16CStr, create_disposition: u32, isDir: bool) -> Result<CreateFileInfo<Option<SevenContext>>, OperationError> {
         let file_name = file_name.to_string_lossy();
-        // 去除首 / 的路径
-        let file_name_match = file_name.trim_start_matches("\\");
-        // 文件实际缓存路径
-        let localFilePath = self.extractPath.join(&file_name_match);
+        // 压缩包中的路径(去除首 / 并加上挂载根目录)
+        let relativePath = self.getRelativePath(&file_name);
+        let file_name_match = relativePath.as_str();
+        // 文件实际缓存路径(上层文件不使用缓存，取基础压缩包的缓存路径)
+        let localFilePath = self.getCachePath().join(&file_name_match);
 
-        // 排除特殊情况(回收站、卷标目录)
-        if file_name.to_lowercase().contains("desktop.ini") || file_name.to_lowercase().contains("recycle.bin") || file_name.to_lowercase().contains("system volume information") {
+        // 排除特殊情况(回收站、卷标目录)，其他过滤规则隐藏的压缩包条目已不在文件列表中，可在上层创建同名文件
+        if self.entryFilter.isSystemEntry(file_name_match) {
             return Err(OperationError::NtStatus(STATUS_OBJECT_NAME_NOT_FOUND));
         }
 
@@ -136,172 +820,196 @@ impl<'a, 'b: 'a> FileSystemHandler<'a, 'b> for ArchiveFS {
             return Ok(CreateFileInfo { context: None, is_dir: true, new_file_created: false });
         }
 
+        // 删除记录可能被其他进程修改(回滚快照)
+        for overlay in self.overlays() {
+            overlay.refresh();
+        }
+
         // writeConsole(ConsoleType::Info, &*format!("Create file: {}, createDisposition: {}, file_attributes: {}", file_name, create_disposition, file_attributes));
 
         // 目前问题：FILE_OPEN 需要创建文件，但按照文档要求是直接报错。如创建文件则二进制程序无法运行(对应配置文件干扰)、创建文件无限循环
         // 已知 memFS也存在此问题
         // 思路：过滤所有系统文件，FILE_OPEN 直接创建文件
 
-        match create_disposition {
-            FILE_OPEN | FILE_OPEN_IF => {
-                // 匹配文件列表
-                for item in self.archiveFileInfoList.iter() {
-                    if file_name_match.to_lowercase() == item.Path.to_lowercase() {
-                        // 更新缓存列表
-                        let mut cacheList = self.cacheInfoList.lock().unwrap();
-                        let _ = cacheList.get(item);
-                        // 返回基本信息
-                        return Ok(CreateFileInfo {
-                            context: Some(SevenContext { localFilePath, FileInfo: item.clone() }),
-                            is_dir: item.is_dir,
-                            new_file_created: false,
-                        });
-                    }
-                }
-
-                // 过滤 系统文件、无关文件
-                if create_disposition == FILE_OPEN || create_disposition == FILE_OVERWRITE {
-                    if self.isDebugMode {
-                        writeConsole(ConsoleType::Warning, &*format!("Create file failed: {}, createDisposition: {}", file_name, create_disposition));
-                    }
-                    return Err(OperationError::NtStatus(STATUS_OBJECT_NAME_NOT_FOUND));
-                }
+        // 匹配文件(上层文件优先于压缩包文件)
+        let upperFilePath = self.getUpperFilePath(file_name_match);
+        let upperExists = !self.readOnly && upperFilePath.exists();
+        let archiveFile = if upperExists { None } else { self.findArchiveFile(file_name_match) };
 
-                if !localFilePath.exists() {
-                    if info.is_dir() { fs::create_dir_all(&localFilePath).ok(); } else { fs::File::create(&localFilePath).ok(); }
-                    // unsafe {
-                    //     let fileName = localFilePath.as_os_str().encode_wide().chain(once(0)).collect::<Vec<u16>>();
-                    //     let handle = winapi::um::fileapi::CreateFileW(fileName.as_ptr(), desired_access, share_access, null_mut(), create_disposition, file_attributes, NULL);
-                    //     if handle == INVALID_HANDLE_VALUE {
-                    //         return Err(OperationError::NtStatus(STATUS_OBJECT_NAME_NOT_FOUND));
-                    //     }
-                    // }
+        if upperExists || archiveFile.is_some() {
+            let isDir = if upperExists { upperFilePath.is_dir() } else { archiveFile.as_ref().map(|item| item.is_dir).unwrap_or(false) };
+            match create_disposition {
+                FILE_CREATE => {
+                    return Err(OperationError::NtStatus(STATUS_OBJECT_NAME_COLLISION));
                 }
-
-                // 判断文件是否位于临时目录(由程序写出)
-                if localFilePath.exists() {
+                FILE_SUPERSEDE | FILE_OVERWRITE | FILE_OVERWRITE_IF if !isDir => {
+                    if self.readOnly {
+                        return Err(OperationError::NtStatus(STATUS_ACCESS_DENIED));
+                    }
+                    // 覆盖文件: 在上层创建空文件
+                    let upperFilePath = self.createUpperEntry(file_name_match, false)?;
                     return Ok(CreateFileInfo {
-                        context: Some(SevenContext {
-                            localFilePath: localFilePath.clone(),
-                            FileInfo: ArchiveFileInfo {
-                                Path: file_name_match.to_string(),
-                                Size: localFilePath.metadata().unwrap().len(),
-                                PackedSize: 0,
-                                Modified: "".to_string(),
-                                Created: None,
-                                is_dir: info.is_dir(),
-                            },
-                        }),
-                        is_dir: info.is_dir(),
+                        context: Some(self.getUpperContext(file_name_match, &localFilePath, &upperFilePath)),
+                        is_dir: false,
                         new_file_created: false,
                     });
                 }
+                _ => {}
             }
-            FILE_CREATE | FILE_OVERWRITE_IF => {
-                // 尝试创建文件
-                if !localFilePath.exists() {
-                    if info.is_dir() { fs::create_dir_all(&localFilePath).ok(); } else { fs::File::create(&localFilePath).ok(); }
-                }
+
+            if let Some(item) = archiveFile {
+                // 更新缓存列表
+                let mut cacheList = self.cacheInfoList.lock().unwrap();
+                let _ = cacheList.get(&item);
+                // 返回基本信息
                 return Ok(CreateFileInfo {
-                    context: Some(SevenContext {
-                        localFilePath: localFilePath.clone(),
-                        FileInfo: ArchiveFileInfo {
-                            Path: file_name_match.to_string(),
-                            Size: 0,
-                            PackedSize: 0,
-                            Modified: "".to_string(),
-                            Created: None,
-                            is_dir: info.is_dir(),
-                        },
-                    }),
-                    is_dir: info.is_dir(),
+                    context: Some(SevenContext { localFilePath: self.getLocalFilePath(&item), FileInfo: item.clone() }),
+                    is_dir: item.is_dir,
                     new_file_created: false,
                 });
             }
-            _ => {}
+
+            // 文件位于上层目录(由程序写出)
+            return Ok(CreateFileInfo {
+                context: Some(self.getUpperContext(file_name_match, &localFilePath, &upperFilePath)),
+                is_dir: isDir,
+                new_file_created: false,
+            });
         }
 
-        if self.isDebugMode {
-            writeConsole(ConsoleType::Warning, &*format!("Create file failed: {}, createDisposition: {}", file_name, create_disposition));
+        // 过滤 系统文件、无关文件
+        if create_disposition == FILE_OPEN || create_disposition == FILE_OVERWRITE {
+            if self.isDebugMode {
+                self.emit(ConsoleType::Warning, &*format!("Create file failed: {}, createDisposition: {}", file_name, create_disposition));
+            }
+            return Err(OperationError::NtStatus(STATUS_OBJECT_NAME_NOT_FOUND));
+        }
+        if self.readOnly {
+            return Err(OperationError::NtStatus(STATUS_ACCESS_DENIED));
+        }
+
+        // 上级目录不存在
+        if !self.isDirectoryExists(getParentPath(file_name_match)) {
+            return Err(OperationError::NtStatus(STATUS_OBJECT_PATH_NOT_FOUND));
+        }
+
+        // 在上层创建文件
+        let upperFilePath = self.createUpperEntry(file_name_match, isDir)?;
+        Ok(CreateFileInfo {
+            context: Some(self.getUpperContext(file_name_match, &localFilePath, &upperFilePath)),
+            is_dir: isDir,
+            new_file_created: true,
+        })
+    }
+
+    /// 将目录及其子项全部复制到上层(重命名目录前调用)
+    fn copyUpDirectory(&self, relativePath: &str) -> Result<(), OperationError> {
+        let lowerPath = format!("{}\\", relativePath.to_lowercase());
+        for item in self.fileList().iter() {
+            if !item.Path.to_lowercase().starts_with(&lowerPath) || self.isDeleted(&item.Path) {
+                continue;
+            }
+            self.copyUp(&item.Path, item, &self.getLocalFilePath(item))?;
         }
-        Err(OperationError::NtStatus(STATUS_OBJECT_NAME_NOT_FOUND))
+        fs::create_dir_all(self.getUpperFilePath(relativePath)).map_err(|_| OperationError::NtStatus(STATUS_ACCESS_DENIED))?;
+        Ok(())
     }
 
-    fn cleanup(&'b self, _file_name: &U16CStr, _info: &OperationInfo<'a, 'b, Self>, _context: &'a Self::Context) {}
+    /// 生成上层文件的上下文
+    fn getUpperContext(&self, relativePath: &str, localFilePath: &Path, upperFilePath: &Path) -> SevenContext {
+        SevenContext {
+            localFilePath: localFilePath.to_path_buf(),
+            FileInfo: ArchiveFileInfo {
+                Path: relativePath.to_string(),
+                Size: upperFilePath.metadata().map(|metadata| metadata.len()).unwrap_or(0),
+                PackedSize: 0,
+                Modified: "".to_string(),
+                Created: None,
+                is_dir: upperFilePath.is_dir(),
+                Layer: 0,
+                CRC: "".to_string(),
+            },
+        }
+    }
+}
 
-    fn close_file(&'b self, _file_name: &U16CStr, _info: &OperationInfo<'a, 'b, Self>, _context: &'a Self::Context) {}
+#[derive(Debug, Clone)]
+pub struct SevenContext {
+    FileInfo: ArchiveFileInfo,
+    localFilePath: PathBuf,
+}
+
+impl<'a, 'b: 'a> FileSystemHandler<'a, 'b> for ArchiveFS {
+    type Context = Option<SevenContext>;
+
+    /// 创建文件对象时调用
+    fn create_file(&'b self, file_name: &U16CStr, _security_context: &DOKAN_IO_SECURITY_CONTEXT, _desired_access: u32, _file_attributes: u32, _share_access: u32, create_disposition: u32, _create_options: u32, info: &mut OperationInfo<'a, 'b, Self>) -> Result<CreateFileInfo<Self::Context>, OperationError> {
+        self.stats.touch();
+        let result = self.createFile(file_name, create_disposition, info.is_dir());
+        // 成功打开的句柄在 close_file 中关闭
+        if result.is_ok() {
+            self.stats.openHandles.fetch_add(1, Ordering::SeqCst);
+        }
+        result
+    }
+
+    /// 关闭句柄时调用(标记删除的文件在此处实际删除)
+    fn cleanup(&'b self, file_name: &U16CStr, info: &OperationInfo<'a, 'b, Self>, context: &'a Self::Context) {
+        let file_name = file_name.to_string_lossy();
+        if context.is_some() && info.delete_on_close() && !self.readOnly {
+            self.removeEntry(&self.getRelativePath(&file_name));
+        }
+    }
+
+    fn close_file(&'b self, _file_name: &U16CStr, _info: &OperationInfo<'a, 'b, Self>, _context: &'a Self::Context) {
+        self.stats.touch();
+        self.stats.openHandles.fetch_sub(1, Ordering::SeqCst);
+    }
 
     /// 读取文件
     fn read_file(&'b self, file_name: &U16CStr, offset: i64, buffer: &mut [u8], _info: &OperationInfo<'a, 'b, Self>, context: &'a Self::Context) -> Result<u32, OperationError> {
+        self.stats.touch();
         let file_name = file_name.to_string_lossy();
         if let Some(context) = context {
             if context.FileInfo.is_dir {
                 return Err(OperationError::NtStatus(STATUS_INVALID_DEVICE_REQUEST));
             }
 
-            if !context.localFilePath.exists() {
-                let mut cacheList = self.cacheInfoList.lock().unwrap();
-                // 自动清理缓存(循环当 缓存总大小 + 当前需要解压文件大小 >= 设置缓存大小)
-                while (cacheList.iter().map(|item| item.0.Size).sum::<u64>() + context.FileInfo.Size) / 1024 / 1024 >= self.cacheSize as u64 {
-                    if let Some(lruInfo) = cacheList.pop_lru() {
-                        if self.isDebugMode { writeConsole(ConsoleType::Info, &*format!("Delete Cache: {}", &lruInfo.1.display())); }
-                        fs::remove_file(&lruInfo.1).ok();
-                    } else {
-                        break;
-                    }
-                }
-
-                // 解压文件
-                let file_name = file_name.trim_start_matches("\\");
-                if self.isDebugMode {
-                    writeConsole(ConsoleType::Info, &*format!("Extracting file: {}\\{}", &*self.archivePath.to_str().unwrap(), &*file_name));
-                }
-                if !self.sevenZip.extractFilesFromPath(&*self.archivePath, self.password.as_deref(), &*file_name, &self.extractPath).unwrap() && !context.localFilePath.exists() {
-                    if self.isDebugMode {
-                        writeConsole(ConsoleType::Warning, &*format!("Extract file failed: {}\\{}", &*self.archivePath.to_str().unwrap(), &*file_name));
-                    }
-                    return Err(OperationError::NtStatus(STATUS_INVALID_DEVICE_REQUEST));
-                }
-
-                // 增加缓存信息
-                cacheList.put(context.FileInfo.clone(), context.localFilePath.clone());
-            }
-            if !context.localFilePath.exists() {
+            // 优先读取上层文件，否则从压缩包解压
+            let upperFilePath = self.getUpperFilePath(&self.getRelativePath(&file_name));
+            let readFilePath = if !self.readOnly && upperFilePath.exists() {
+                upperFilePath
+            } else {
+                self.extractFile(&context.FileInfo, &context.localFilePath)?;
+                context.localFilePath.clone()
+            };
+            if !readFilePath.exists() {
                 return Err(OperationError::NtStatus(STATUS_INVALID_DEVICE_REQUEST));
             }
 
-            let file = fs::File::open(&context.localFilePath).unwrap();
-            let result = file.seek_read(buffer, offset as u64).unwrap();
+            let file = fs::File::open(&readFilePath).map_err(|_| OperationError::NtStatus(STATUS_INVALID_DEVICE_REQUEST))?;
+            let result = file.seek_read(buffer, offset as u64).map_err(|_| OperationError::NtStatus(STATUS_INVALID_DEVICE_REQUEST))?;
+            self.stats.readBytes.fetch_add(result as u64, Ordering::Relaxed);
             return Ok(result as u32);
         }
         if self.isDebugMode {
-            writeConsole(ConsoleType::Warning, &*format!("Read file failed: {}", file_name));
+            self.emit(ConsoleType::Warning, &*format!("Read file failed: {}", file_name));
         }
         Err(OperationError::NtStatus(STATUS_INVALID_DEVICE_REQUEST))
     }
 
     /// 写入文件
     fn write_file(&'b self, file_name: &U16CStr, offset: i64, buffer: &[u8], info: &OperationInfo<'a, 'b, Self>, context: &'a Self::Context) -> Result<u32, OperationError> {
+        self.stats.touch();
         let file_name = file_name.to_string_lossy();
-
-        if let Some(context) = context {
-            if info.is_dir() {
-                fs::create_dir_all(&context.localFilePath).ok();
-                return Ok(0);
-                // return Err(OperationError::NtStatus(STATUS_INVALID_DEVICE_REQUEST));
-            }
-            // 以追加模式打开文件并尝试写入
-            if let Ok(file) = OpenOptions::new().append(true).open(&context.localFilePath) {
-                if let Ok(result) = file.seek_write(buffer, offset as u64) {
-                    return Ok(result as u32);
-                }
-            }
-        }
-
-        if self.isDebugMode {
-            writeConsole(ConsoleType::Warning, &*format!("Write file failed: {}", file_name));
+        let result = match context {
+            Some(context) => self.writeFile(&self.getRelativePath(&file_name), context, offset, buffer, info.write_to_eof(), info.paging_io()),
+            None => Err(OperationError::NtStatus(STATUS_ACCESS_DENIED)),
+        };
+        if result.is_err() && self.isDebugMode {
+            self.emit(ConsoleType::Warning, &*format!("Write file failed: {}", file_name));
         }
-        Err(OperationError::NtStatus(STATUS_ACCESS_DENIED))
+        result
     }
 
     /// 获取文件信息
@@ -313,6 +1021,21 @@ impl<'a, 'b: 'a> FileSystemHandler<'a, 'b> for ArchiveFS {
         }
 
         if let Some(context) = context {
+            // 上层文件使用实际文件信息
+            if !self.readOnly {
+                if let Ok(metadata) = self.getUpperFilePath(&self.getRelativePath(&file_name)).metadata() {
+                    return Ok(FileInfo {
+                        attributes: if metadata.is_dir() { FILE_ATTRIBUTES_DIRECTORY } else { FILE_ATTRIBUTES_NORMAL },
+                        creation_time: metadata.created().unwrap_or_else(|_| SystemTime::now()),
+                        last_access_time: metadata.accessed().unwrap_or_else(|_| SystemTime::now()),
+                        last_write_time: metadata.modified().unwrap_or_else(|_| SystemTime::now()),
+                        file_size: if metadata.is_dir() { 0 } else { metadata.len() },
+                        number_of_links: 0,
+                        file_index: 0,
+                    });
+                }
+            }
+
             let modifiedTime = StringToSystemTime(&*context.FileInfo.Modified).unwrap_or_else(|_| SystemTime::now());
             return Ok(FileInfo {
                 attributes: if context.FileInfo.is_dir { FILE_ATTRIBUTES_DIRECTORY } else { FILE_ATTRIBUTES_NORMAL },
@@ -325,55 +1048,66 @@ impl<'a, 'b: 'a> FileSystemHandler<'a, 'b> for ArchiveFS {
             });
         }
         if self.isDebugMode {
-            writeConsole(ConsoleType::Warning, &*format!("Read fileInfo failed: {}", file_name));
+            self.emit(ConsoleType::Warning, &*format!("Read fileInfo failed: {}", file_name));
         }
         Err(OperationError::NtStatus(STATUS_NDIS_FILE_NOT_FOUND))
     }
 
     /// 列出目录中的所有子项
     fn find_files(&'b self, file_name: &U16CStr, mut fill_find_data: impl FnMut(&FindData) -> Result<(), FillDataError>, _info: &OperationInfo<'a, 'b, Self>, _context: &'a Self::Context) -> Result<(), OperationError> {
-        let matchPath = file_name.to_string_lossy();
-        let mut totalFiles = Vec::new();
+        let matchPath = self.getRelativePath(&file_name.to_string_lossy()).to_lowercase();
+        self.indexLayer(&matchPath, true);
+        // 已列出的文件名(小写)
+        let mut totalFiles = HashSet::new();
 
         // 列出压缩包内部文件
-        for item in self.archiveFileInfoList.iter() {
-            let filePath = format!(r"\{}", item.Path);
-            // 筛选出匹配的文件(前面匹配、不等于自身、父路径匹配)
-            if filePath.find(&matchPath) == Some(0) && filePath != matchPath && Path::new(&filePath).parent().unwrap().to_str().unwrap() == matchPath {
-                let fileName = Path::new(&item.Path).file_name().unwrap().to_str().unwrap();
-                let modifiedTime = StringToSystemTime(&*item.Modified).unwrap_or_else(|_| SystemTime::now());
-                totalFiles.push(&*item.Path);
-                fill_find_data(&FindData {
-                    attributes: if item.is_dir { FILE_ATTRIBUTES_DIRECTORY } else { FILE_ATTRIBUTES_NORMAL },
-                    creation_time: modifiedTime,
-                    last_access_time: modifiedTime,
-                    last_write_time: modifiedTime,
-                    file_size: item.Size,
-                    file_name: convert_str(fileName),
-                })?;
-            }
-        }
-
-        // 列出缓存目录文件
-        if !self.readOnly {
-            let path = self.extractPath.join(matchPath.trim_start_matches("\\"));
-            if path.exists() {
-                for entry in path.read_dir().unwrap() {
-                    if let Ok(item) = entry {
-                        // 排除临时解压的文件
-                        if totalFiles.iter().filter(|relativePath| item.path().ends_with(relativePath)).count() > 0 {
-                            continue;
-                        }
-                        let metadata = &item.path().metadata().unwrap();
-                        fill_find_data(&FindData {
-                            attributes: if item.path().is_dir() { FILE_ATTRIBUTES_DIRECTORY } else { FILE_ATTRIBUTES_NORMAL },
-                            creation_time: SystemTime::now(),
-                            last_access_time: SystemTime::now(),
-                            last_write_time: SystemTime::now(),
-                            file_size: metadata.len(),
-                            file_name: convert_str(item.path().file_name().unwrap().to_str().unwrap()),
-                        })?;
+        for item in self.fileList().iter() {
+            // 筛选出父路径匹配且未删除的文件
+            if getParentPath(&item.Path).to_lowercase() != matchPath || self.isDeleted(&item.Path) {
+                continue;
+            }
+            let fileName = Path::new(&item.Path).file_name().unwrap().to_str().unwrap();
+            // 已被修改的文件由上层目录列出
+            if !self.readOnly && self.getUpperFilePath(&item.Path).exists() {
+                continue;
+            }
+            totalFiles.insert(fileName.to_lowercase());
+            let modifiedTime = StringToSystemTime(&*item.Modified).unwrap_or_else(|_| SystemTime::now());
+            fill_find_data(&FindData {
+                attributes: if item.is_dir { FILE_ATTRIBUTES_DIRECTORY } else { FILE_ATTRIBUTES_NORMAL },
+                creation_time: modifiedTime,
+                last_access_time: modifiedTime,
+                last_write_time: modifiedTime,
+                file_size: item.Size,
+                file_name: convert_str(fileName),
+            })?;
+        }
+
+        // 列出各覆盖层的上层目录文件
+        for overlay in self.overlays().filter(|_| !self.readOnly) {
+            let upperDirPath = overlay.getUpperFilePath(&matchPath);
+            if !upperDirPath.is_dir() {
+                continue;
+            }
+            for item in upperDirPath.read_dir().unwrap().flatten() {
+                let fileName = item.file_name().to_string_lossy().to_string();
+                if totalFiles.contains(&fileName.to_lowercase()) || self.entryFilter.isSystemEntry(&format!("{}\\{}", matchPath, fileName)) {
+                    continue;
+                }
+                if let Ok(metadata) = item.metadata() {
+                    // 文件仅由其所属的覆盖层列出(目录可能同时存在于多个覆盖层)
+                    if !metadata.is_dir() && !std::ptr::eq(self.getOverlay(&format!("{}\\{}", matchPath, fileName)), overlay) {
+                        continue;
                     }
+                    totalFiles.insert(fileName.to_lowercase());
+                    fill_find_data(&FindData {
+                        attributes: if metadata.is_dir() { FILE_ATTRIBUTES_DIRECTORY } else { FILE_ATTRIBUTES_NORMAL },
+                        creation_time: metadata.created().unwrap_or_else(|_| SystemTime::now()),
+                        last_access_time: metadata.accessed().unwrap_or_else(|_| SystemTime::now()),
+                        last_write_time: metadata.modified().unwrap_or_else(|_| SystemTime::now()),
+                        file_size: if metadata.is_dir() { 0 } else { metadata.len() },
+                        file_name: convert_str(&fileName),
+                    })?;
                 }
             }
         }
@@ -418,7 +1152,9 @@ impl<'a, 'b: 'a> FileSystemHandler<'a, 'b> for ArchiveFS {
         //     writeConsole(ConsoleType::Err, "Mounted archive failed");
         //     process::exit(0x0100);
         // }
-        writeConsole(ConsoleType::Success, "Mounted archive successfully");
+        self.stats.mounted.store(true, Ordering::SeqCst);
+        self.stats.touch();
+        emitEvent(self.eventHandler.as_ref(), ConsoleEvent::mounted { mountPath: mount_point.to_string(), message: "Mounted archive successfully".to_string() });
         if self.open {
             let _ = Command::new("explorer").arg(mount_point).output().unwrap();
         }
@@ -427,47 +1163,126 @@ impl<'a, 'b: 'a> FileSystemHandler<'a, 'b> for ArchiveFS {
 
     /// 卸载后钩子
     fn unmounted(&'b self, info: &OperationInfo<'a, 'b, Self>) -> Result<(), OperationError> {
+        self.stats.mounted.store(false, Ordering::SeqCst);
         let mount_point = info.mount_point().unwrap().to_string_lossy();
         // 尝试卸载虚拟盘符
         removeVirtualDrive(Path::new(&mount_point));
         // 尝试删除挂载目录
         fs::remove_dir_all(&mount_point).ok();
+        // 提交覆盖层的修改(清理覆盖层之前)
+        self.commitUnmountChanges();
         // 清理缓存目录
-        fs::remove_dir_all(&self.extractPath).ok();
+        for layer in self.layers().iter() {
+            fs::remove_dir_all(&layer.extractPath).ok();
+        }
+        if let Some(archiveDirectory) = &self.archiveDirectory {
+            fs::remove_dir_all(&archiveDirectory.extractPath).ok();
+        }
+        // 清理覆盖层(持久覆盖层保留至下次挂载，请求提交时由提交修改的进程清理)
+        let commitRequested = isCommitRequested(Path::new(&*mount_point));
+        for overlay in self.overlays().filter(|overlay| !overlay.isPersistent()) {
+            if commitRequested && overlay.rootPath() == self.overlay.rootPath() {
+                continue;
+            }
+            fs::remove_dir_all(overlay.rootPath()).ok();
+        }
         Ok(())
     }
 
+    /// 检查文件能否删除(实际删除在 cleanup 中进行)
     fn delete_file(&'b self, file_name: &U16CStr, _info: &OperationInfo<'a, 'b, Self>, context: &'a Self::Context) -> Result<(), OperationError> {
         let file_name = file_name.to_string_lossy();
         if let Some(context) = context {
-            if context.localFilePath.exists() {
-                fs::remove_file(&context.localFilePath).ok();
-                return Ok(());
+            if !self.readOnly && !context.FileInfo.is_dir {
+                return self.checkWritable(&self.getRelativePath(&file_name));
             }
         }
-        writeConsole(ConsoleType::Warning, &*format!("Delete file failed: {}", file_name));
+        self.emit(ConsoleType::Warning, &*format!("Delete file failed: {}", file_name));
         Err(OperationError::NtStatus(STATUS_ACCESS_DENIED))
     }
 
-    fn delete_directory(&'b self, file_name: &U16CStr, _info: &OperationInfo<'a, 'b, Self>, _context: &'a Self::Context) -> Result<(), OperationError> {
+    /// 检查目录能否删除(仅允许删除空目录，实际删除在 cleanup 中进行)
+    fn delete_directory(&'b self, file_name: &U16CStr, info: &OperationInfo<'a, 'b, Self>, context: &'a Self::Context) -> Result<(), OperationError> {
         let file_name = file_name.to_string_lossy();
-        writeConsole(ConsoleType::Warning, &*format!("Delete directory failed: {}", file_name));
+        if context.is_some() && !self.readOnly {
+            let relativePath = self.getRelativePath(&file_name);
+            if !info.delete_on_close() {
+                return self.checkWritable(&relativePath);
+            }
+            return self.checkDeleteDirectory(&relativePath);
+        }
+        self.emit(ConsoleType::Warning, &*format!("Delete directory failed: {}", file_name));
         Err(OperationError::NtStatus(STATUS_ACCESS_DENIED))
     }
 
-    fn move_file(&'b self, file_name: &U16CStr, _new_file_name: &U16CStr, _replace_if_existing: bool, _info: &OperationInfo<'a, 'b, Self>, _context: &'a Self::Context) -> Result<(), OperationError> {
+    /// 移动(重命名)文件: 复制到上层后移动，原路径存在于压缩包时记录删除
+    fn move_file(&'b self, file_name: &U16CStr, new_file_name: &U16CStr, replace_if_existing: bool, _info: &OperationInfo<'a, 'b, Self>, context: &'a Self::Context) -> Result<(), OperationError> {
         let file_name = file_name.to_string_lossy();
-        writeConsole(ConsoleType::Warning, &*format!("Move file failed: {}", file_name));
+        let new_file_name = new_file_name.to_string_lossy();
+        let relativePath = &*self.getRelativePath(&file_name);
+        let newRelativePath = &*self.getRelativePath(&new_file_name);
+
+        if let Some(context) = context {
+            if !self.readOnly {
+                self.checkWritable(newRelativePath)?;
+                // 目标已存在
+                let newUpperFilePath = self.getUpperFilePath(newRelativePath);
+                let newExists = newUpperFilePath.exists() || self.findArchiveFile(newRelativePath).is_some();
+                if newExists && (!replace_if_existing || context.FileInfo.is_dir) {
+                    return Err(OperationError::NtStatus(STATUS_OBJECT_NAME_COLLISION));
+                }
+                if !self.isDirectoryExists(getParentPath(newRelativePath)) {
+                    return Err(OperationError::NtStatus(STATUS_OBJECT_PATH_NOT_FOUND));
+                }
+
+                // 将源文件复制到上层
+                let upperFilePath = if context.FileInfo.is_dir {
+                    self.copyUpDirectory(relativePath)?;
+                    self.getUpperFilePath(relativePath)
+                } else {
+                    self.copyUp(relativePath, &context.FileInfo, &context.localFilePath)?
+                };
+
+                // 移动上层文件
+                if newUpperFilePath.is_file() {
+                    fs::remove_file(&newUpperFilePath).ok();
+                }
+                self.getOverlay(newRelativePath).removeWhiteout(newRelativePath);
+                if let Some(parent) = newUpperFilePath.parent() {
+                    fs::create_dir_all(parent).ok();
+                }
+                // 源与目标位于不同覆盖层时可能跨磁盘，文件改为复制后删除
+                let moved = fs::rename(&upperFilePath, &newUpperFilePath).is_ok()
+                    || (upperFilePath.is_file() && fs::copy(&upperFilePath, &newUpperFilePath).is_ok() && fs::remove_file(&upperFilePath).is_ok());
+                if moved {
+                    if self.findArchiveFile(relativePath).is_some() {
+                        self.getOverlay(relativePath).addWhiteout(relativePath);
+                    }
+                    return Ok(());
+                }
+            }
+        }
 
+        self.emit(ConsoleType::Warning, &*format!("Move file failed: {}", file_name));
         Err(OperationError::NtStatus(STATUS_ACCESS_DENIED))
     }
 
-    fn set_end_of_file(&'b self, _file_name: &U16CStr, _offset: i64, _info: &OperationInfo<'a, 'b, Self>, _context: &'a Self::Context) -> Result<(), OperationError> {
-        Err(OperationError::NtStatus(STATUS_ACCESS_DENIED))
+    /// 设置文件大小(截断或扩展)
+    fn set_end_of_file(&'b self, file_name: &U16CStr, offset: i64, _info: &OperationInfo<'a, 'b, Self>, context: &'a Self::Context) -> Result<(), OperationError> {
+        let file_name = file_name.to_string_lossy();
+        match context {
+            Some(context) => self.setFileSize(&self.getRelativePath(&file_name), context, offset),
+            None => Err(OperationError::NtStatus(STATUS_ACCESS_DENIED)),
+        }
     }
 
-    fn set_allocation_size(&'b self, _file_name: &U16CStr, _alloc_size: i64, _info: &OperationInfo<'a, 'b, Self>, _context: &'a Self::Context) -> Result<(), OperationError> {
-        Err(OperationError::NtStatus(STATUS_ACCESS_DENIED))
+    /// 设置分配大小(小于文件大小时截断，否则视为预分配)
+    fn set_allocation_size(&'b self, file_name: &U16CStr, alloc_size: i64, _info: &OperationInfo<'a, 'b, Self>, context: &'a Self::Context) -> Result<(), OperationError> {
+        let file_name = file_name.to_string_lossy();
+        match context {
+            Some(context) => self.setAllocationSize(&self.getRelativePath(&file_name), context, alloc_size),
+            None => Err(OperationError::NtStatus(STATUS_ACCESS_DENIED)),
+        }
     }
 
     fn lock_file(&'b self, _file_name: &U16CStr, _offset: i64, _length: i64, _info: &OperationInfo<'a, 'b, Self>, _context: &'a Self::Context) -> Result<(), OperationError> {
@@ -479,6 +1294,45 @@ impl<'a, 'b: 'a> FileSystemHandler<'a, 'b> for ArchiveFS {
     }
 }
 
+/// 已变化的压缩包层: 状态与上次处理时不同，且与上次检测一致(复制完成)时返回变化的层，否则返回 None
+/// 参数1: 上次处理时的状态
+/// 参数2: 上次检测时的状态
+/// 参数3: 当前状态
+pub(crate) fn getChangedLayers<T: PartialEq>(stateList: &[T], pendingList: &[T], currentList: &[T]) -> Option<Vec<usize>> {
+    if currentList == stateList || currentList != pendingList {
+        return None;
+    }
+    Some((0..currentList.len()).filter(|&index| currentList[index] != stateList[index]).collect())
+}
+
+/// 合并压缩包层并按过滤规则过滤条目(保留尚未读取的压缩包子目录)
+fn mergeFileList(layers: &[ArchiveLayer], entryFilter: &EntryFilter) -> Vec<ArchiveFileInfo> {
+    let keepList: Vec<String> = layers.iter().filter(|layer| !layer.indexed).map(|layer| layer.prefix.clone()).collect();
+    entryFilter.apply(mergeLayers(layers), &keepList)
+}
+
+/// 压缩包文件状态(各分卷的大小、修改时间)，任一分卷不存在时返回 None
+fn getArchiveState(volumeList: &[PathBuf]) -> Option<Vec<(u64, SystemTime)>> {
+    volumeList.iter().map(|volumePath| {
+        let metadata = volumePath.metadata().ok()?;
+        Some((metadata.len(), metadata.modified().unwrap_or(UNIX_EPOCH)))
+    }).collect()
+}
+
+/// 压缩包中的路径去除挂载根目录(根目录为空时不变，不在根目录中或为根目录本身时返回 None)
+pub(crate) fn stripRootPath<'p>(rootPath: &str, relativePath: &'p str) -> Option<&'p str> {
+    if rootPath.is_empty() {
+        return Some(relativePath);
+    }
+    let prefix = relativePath.get(..rootPath.len()).filter(|prefix| prefix.to_lowercase() == rootPath.to_lowercase())?;
+    relativePath[prefix.len()..].strip_prefix('\\')
+}
+
+/// 获取父路径(根目录下的条目返回空字符串)
+fn getParentPath(path: &str) -> &str {
+    path.rfind('\\').map(|index| &path[..index]).unwrap_or("")
+}
+
 fn convert_str(s: impl AsRef<str>) -> U16CString {
     unsafe { U16CString::from_str_unchecked(s) }
 }
@@ -0,0 +1,281 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+use chrono::{DateTime, Local};
+
+use crate::overlay::normalizePath;
+use crate::sevenZip::{ArchiveFileInfo, sevenZip};
+use crate::utils::util::getPathHash;
+use crate::volume::{checkVolumes, parseVolumeFileName, resolveVolumes};
+
+/// 补丁压缩包中的删除清单文件名(每行一个被删除的下层条目)
+pub const DELETIONS_FILE_NAME: &str = ".ArchiveMount-deletions.txt";
+
+/// 嵌套压缩包的缓存目录名(位于外层压缩包层的缓存目录中)
+const NESTED_CACHE_NAME: &str = ".ArchiveMount-nested";
+
+/// 目录挂载及展开嵌套压缩包时识别为压缩包的扩展名
+const ARCHIVE_EXTENSIONS: [&str; 20] = ["7z", "zip", "rar", "tar", "gz", "tgz", "bz2", "tbz2", "xz", "txz", "wim", "iso", "cab", "lzh", "arj", "cpio", "rpm", "dmg", "vhd", "zst"];
+
+/// 压缩包层(基础压缩包或叠加在其上的补丁压缩包)
+#[derive(Debug, Clone)]
+pub struct ArchiveLayer {
+    /// 压缩包路径(分卷压缩包为打开压缩包使用的分卷)
+    pub(crate) archivePath: PathBuf,
+    /// 压缩包的所有分卷(非分卷压缩包仅包含其本身)
+    pub(crate) volumeList: Vec<PathBuf>,
+    /// 压缩包密码
+    pub(crate) password: Option<String>,
+    /// 缓存目录
+    pub(crate) extractPath: PathBuf,
+    /// 压缩包文件信息
+    pub(crate) archiveFileInfoList: Vec<ArchiveFileInfo>,
+    /// 删除清单(该层删除的下层条目)
+    pub(crate) deletedList: Vec<String>,
+    /// 子目录名(目录挂载时压缩包显示为该子目录，其他情况为空)
+    pub(crate) prefix: String,
+    /// 是否已读取文件列表(目录挂载时首次访问子目录才读取)
+    pub(crate) indexed: bool,
+    /// 嵌套压缩包的来源(展开嵌套压缩包时设置)
+    pub(crate) source: Option<NestedSource>,
+}
+
+/// 嵌套压缩包的来源(外层压缩包层中的条目)
+#[derive(Debug, Clone)]
+pub struct NestedSource {
+    /// 外层压缩包层
+    pub(crate) layer: usize,
+    /// 外层压缩包中的条目(路径为挂载中的路径)
+    pub(crate) fileInfo: ArchiveFileInfo,
+    /// 嵌套层数(从1开始)
+    pub(crate) depth: u32,
+}
+
+impl ArchiveLayer {
+    /// 读取压缩包文件列表及删除清单
+    pub fn new(sevenZip: &sevenZip, archivePath: &Path, password: Option<&str>, extractPath: &Path) -> Result<ArchiveLayer> {
+        // 分卷压缩包缺少末尾的分卷时读取文件会失败，挂载前检查
+        let volumeList = getVolumeList(archivePath);
+        if volumeList.len() > 1 || isVolume(archivePath) {
+            checkVolumes(&sevenZip.getArchiveInfo(archivePath, password)?, &volumeList)?;
+        }
+        let mut archiveFileInfoList = sevenZip.listArchiveFiles(archivePath, password)?;
+
+        // 读取删除清单
+        let mut deletedList = Vec::new();
+        if let Some(index) = archiveFileInfoList.iter().position(|item| item.Path == DELETIONS_FILE_NAME) {
+            archiveFileInfoList.remove(index);
+            fs::create_dir_all(extractPath)?;
+            sevenZip.extractFilesFromPath(archivePath, password, DELETIONS_FILE_NAME, extractPath)?;
+            let deletionsFilePath = extractPath.join(DELETIONS_FILE_NAME);
+            deletedList = fs::read_to_string(&deletionsFilePath)?.lines()
+                .map(normalizePath)
+                .filter(|path| !path.is_empty())
+                .collect();
+            fs::remove_file(&deletionsFilePath).ok();
+        }
+
+        Ok(ArchiveLayer {
+            archivePath: archivePath.to_path_buf(),
+            volumeList,
+            password: password.map(|password| password.to_string()),
+            extractPath: extractPath.to_path_buf(),
+            archiveFileInfoList,
+            deletedList,
+            prefix: String::new(),
+            indexed: true,
+            source: None,
+        })
+    }
+
+    /// 未读取文件列表的压缩包层(目录挂载)
+    pub fn unindexed(archivePath: &Path, password: Option<&str>, extractPath: &Path, prefix: &str) -> ArchiveLayer {
+        ArchiveLayer {
+            archivePath: archivePath.to_path_buf(),
+            volumeList: getVolumeList(archivePath),
+            password: password.map(|password| password.to_string()),
+            extractPath: extractPath.to_path_buf(),
+            archiveFileInfoList: Vec::new(),
+            deletedList: Vec::new(),
+            prefix: prefix.to_string(),
+            indexed: false,
+            source: None,
+        }
+    }
+
+    /// 读取(或重新读取)文件列表，保留子目录名及嵌套压缩包的来源
+    pub fn index(&self, sevenZip: &sevenZip) -> Result<ArchiveLayer> {
+        let mut layer = ArchiveLayer::new(sevenZip, &self.archivePath, self.password.as_deref(), &self.extractPath)?;
+        layer.prefix = self.prefix.clone();
+        layer.source = self.source.clone();
+        Ok(layer)
+    }
+
+    /// 未读取文件列表的副本(外层压缩包变化后重新读取嵌套压缩包)
+    pub fn unindex(&self) -> ArchiveLayer {
+        let mut layer = ArchiveLayer::unindexed(&self.archivePath, self.password.as_deref(), &self.extractPath, &self.prefix);
+        layer.source = self.source.clone();
+        layer
+    }
+
+    /// 条目在挂载中的路径(加上子目录名)
+    pub fn getMountedPath(&self, path: &str) -> String {
+        if self.prefix.is_empty() { path.to_string() } else { format!("{}\\{}", self.prefix, path) }
+    }
+
+    /// 条目在压缩包中的路径(去除子目录名，子目录本身为空)
+    pub fn getArchivePath<'p>(&self, path: &'p str) -> &'p str {
+        if self.prefix.is_empty() {
+            return path;
+        }
+        path.get(self.prefix.len()..).unwrap_or("").trim_start_matches('\\')
+    }
+}
+
+/// 压缩包目录(目录挂载: 目录中的每个压缩包显示为一个子目录)
+#[derive(Debug, Clone)]
+pub struct ArchiveDirectory {
+    /// 目录路径
+    pub(crate) path: PathBuf,
+    /// 压缩包密码
+    pub(crate) password: Option<String>,
+    /// 缓存目录(每个压缩包使用其中的子目录)
+    pub(crate) extractPath: PathBuf,
+    /// 子目录名是否去除扩展名
+    pub(crate) stripExtension: bool,
+}
+
+impl ArchiveDirectory {
+    /// 列出目录中尚未加入的压缩包(未读取文件列表)
+    pub fn listNewLayers(&self, layers: &[ArchiveLayer]) -> Vec<ArchiveLayer> {
+        let mut archivePathList: Vec<PathBuf> = match self.path.read_dir() {
+            Ok(entries) => entries.flatten().map(|entry| entry.path()).filter(|path| path.is_file() && (isArchiveFile(path) || isPrimaryVolume(path))).collect(),
+            Err(_) => return Vec::new(),
+        };
+        archivePathList.sort();
+        let mut nameList: Vec<String> = layers.iter().map(|layer| layer.prefix.to_lowercase()).collect();
+        let mut newLayers = Vec::new();
+        for archivePath in archivePathList.iter().filter(|archivePath| !layers.iter().any(|layer| &layer.archivePath == *archivePath)) {
+            // 分卷压缩包仅列出打开压缩包使用的分卷，以压缩包名称命名(Game.7z.001 显示为 Game.7z)
+            let fileName = match parseVolumeFileName(archivePath.file_name().unwrap().to_str().unwrap()) {
+                Some((_, false)) => continue,
+                Some((setName, true)) => setName,
+                None => archivePath.file_name().unwrap().to_str().unwrap().to_string(),
+            };
+            let fileName = fileName.as_str();
+            let mut name = if self.stripExtension { Path::new(fileName).file_stem().unwrap().to_str().unwrap() } else { fileName };
+            // 去除扩展名后重名时保留扩展名
+            if nameList.contains(&name.to_lowercase()) {
+                name = fileName;
+            }
+            if nameList.contains(&name.to_lowercase()) {
+                continue;
+            }
+            nameList.push(name.to_lowercase());
+            newLayers.push(ArchiveLayer::unindexed(archivePath, self.password.as_deref(), &self.extractPath.join(name), name));
+        }
+        newLayers
+    }
+}
+
+/// 列出压缩包层中尚未加入的嵌套压缩包(未读取文件列表，首次访问时解压至外层的缓存目录后读取)
+/// 参数3: 最大嵌套层数
+pub fn listNestedLayers(layers: &[ArchiveLayer], layerIndex: usize, maxDepth: u32) -> Vec<ArchiveLayer> {
+    let layer = &layers[layerIndex];
+    let depth = layer.source.as_ref().map(|source| source.depth).unwrap_or(0) + 1;
+    if !layer.indexed || depth > maxDepth {
+        return Vec::new();
+    }
+    let mut nestedLayers = Vec::new();
+    // 分卷压缩包不展开
+    for item in layer.archiveFileInfoList.iter().filter(|item| !item.is_dir && isArchiveFile(Path::new(&item.Path)) && parseVolumeFileName(getFileName(&item.Path)).is_none()) {
+        let path = layer.getMountedPath(&item.Path);
+        if layers.iter().any(|layer| layer.prefix.to_lowercase() == path.to_lowercase()) {
+            continue;
+        }
+        let extractPath = layer.extractPath.join(NESTED_CACHE_NAME).join(getPathHash(Path::new(&item.Path)));
+        let mut nestedLayer = ArchiveLayer::unindexed(&layer.extractPath.join(&item.Path), layer.password.as_deref(), &extractPath, &path);
+        let fileInfo = ArchiveFileInfo { Path: path, Layer: layerIndex, ..item.clone() };
+        nestedLayer.source = Some(NestedSource { layer: layerIndex, fileInfo, depth });
+        nestedLayers.push(nestedLayer);
+    }
+    nestedLayers
+}
+
+/// 是否为压缩包文件(按扩展名)
+fn isArchiveFile(path: &Path) -> bool {
+    let extension = path.extension().and_then(|extension| extension.to_str()).unwrap_or("").to_lowercase();
+    ARCHIVE_EXTENSIONS.contains(&&*extension)
+}
+
+/// 是否为分卷压缩包中打开压缩包使用的分卷(如 Game.7z.001)
+fn isPrimaryVolume(path: &Path) -> bool {
+    let fileName = path.file_name().and_then(|fileName| fileName.to_str()).unwrap_or("");
+    parseVolumeFileName(fileName).map(|(_, isPrimary)| isPrimary).unwrap_or(false)
+}
+
+/// 是否为分卷(按文件名)
+fn isVolume(path: &Path) -> bool {
+    path.file_name().and_then(|fileName| fileName.to_str()).and_then(parseVolumeFileName).is_some()
+}
+
+/// 压缩包的所有分卷(缺少分卷时仅包含其本身)
+fn getVolumeList(archivePath: &Path) -> Vec<PathBuf> {
+    resolveVolumes(archivePath).map(|volumeSet| volumeSet.volumeList).unwrap_or_else(|_| vec![archivePath.to_path_buf()])
+}
+
+/// 压缩包内路径的文件名
+fn getFileName(path: &str) -> &str {
+    path.rsplit('\\').next().unwrap_or(path)
+}
+
+/// 合并压缩包层: 按顺序叠加，上层条目按路径覆盖下层条目，删除清单移除下层条目
+pub fn mergeLayers(layers: &[ArchiveLayer]) -> Vec<ArchiveFileInfo> {
+    let mut mergedList: Vec<ArchiveFileInfo> = Vec::new();
+    for (layerIndex, layer) in layers.iter().enumerate() {
+        // 目录挂载中已删除的压缩包不显示
+        if !layer.prefix.is_empty() && layer.source.is_none() && !layer.archivePath.is_file() {
+            continue;
+        }
+        // 外层压缩包中已不存在(或被上层覆盖)的嵌套压缩包不显示
+        if let Some(source) = &layer.source {
+            let prefix = layer.prefix.to_lowercase();
+            if !mergedList.iter().any(|item| item.Layer == source.layer && !item.is_dir && item.Path.to_lowercase() == prefix) {
+                continue;
+            }
+        }
+        // 应用删除清单(删除目录时同时删除其子项)
+        if !layer.deletedList.is_empty() {
+            let deletedList: Vec<String> = layer.deletedList.iter().map(|path| layer.getMountedPath(path).to_lowercase()).collect();
+            mergedList.retain(|item| {
+                let path = item.Path.to_lowercase();
+                !deletedList.iter().any(|deletedPath| path == *deletedPath || path.starts_with(&format!("{}\\", deletedPath)))
+            });
+        }
+
+        let mut indexMap: HashMap<String, usize> = mergedList.iter().enumerate().map(|(index, item)| (item.Path.to_lowercase(), index)).collect();
+        // 目录挂载及嵌套压缩包显示为子目录(修改时间取压缩包的修改时间)
+        let directoryItem = if layer.prefix.is_empty() { None } else {
+            let modified = match &layer.source {
+                Some(source) => source.fileInfo.Modified.clone(),
+                None => fs::metadata(&layer.archivePath).and_then(|metadata| metadata.modified()).map(|modified| DateTime::<Local>::from(modified).format("%Y-%m-%d %H:%M:%S").to_string()).unwrap_or_default(),
+            };
+            Some(ArchiveFileInfo { Path: String::new(), Size: 0, PackedSize: 0, Modified: modified, Created: None, is_dir: true, Layer: layerIndex, CRC: String::new() })
+        };
+        for item in directoryItem.iter().chain(layer.archiveFileInfoList.iter()) {
+            let mut item = item.clone();
+            item.Layer = layerIndex;
+            item.Path = layer.getMountedPath(&item.Path).trim_end_matches('\\').to_string();
+            let path = item.Path.to_lowercase();
+            if let Some(&index) = indexMap.get(&path) {
+                mergedList[index] = item;
+            } else {
+                indexMap.insert(path, mergedList.len());
+                mergedList.push(item);
+            }
+        }
+    }
+    mergedList
+}
@@ -1,13 +1,21 @@
-use std::{env, fs};
-use std::path::PathBuf;
+use std::{env, fs, process};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 
-use clap::{Parser, Subcommand};
-use dokan::MountError;
+use chrono::{DateTime, Local};
+use clap::{CommandFactory, FromArgMatches, Parser, Subcommand};
 
-use crate::{ArchiveFS, TEMP_PATH};
-use crate::sevenZip::sevenZip;
-use crate::utils::console::{ConsoleType, writeConsole};
-use crate::utils::util::createVirtualDrive;
+use ArchiveMount::{ArchiveFS, MountBuilder, MountFailed, MountHandle, MountManifest, TEMP_PATH};
+use ArchiveMount::launcher::runProgram;
+use ArchiveMount::manager::{isManagerRunning, listMountStatus, Manager, ManagerRequest, MountStatus, sendRequest};
+use ArchiveMount::mountOptions::MountOptions;
+use ArchiveMount::overlay::Overlay;
+use ArchiveMount::registry::{cleanupStaleMounts, findMountRecord, MountRecord, removeOrphanedPaths};
+use ArchiveMount::sevenZip::sevenZip;
+use ArchiveMount::utils::console::{ConsoleEvent, ConsoleType, isJsonOutput, OutputFormat, setExitCode, setOutputFormat, writeConsole, writeError, writeEvent};
+
+/// 卸载后等待挂载结束(释放覆盖层)的超时时间
+const UNMOUNT_TIMEOUT: Duration = Duration::from_secs(30);
 
 #[derive(Parser, Debug)]
 #[clap(version)]
@@ -15,140 +23,576 @@ use crate::utils::util::createVirtualDrive;
 struct Cli {
     #[clap(subcommand)]
     command: Commands,
+    /// Output format(json: one event per line)
+    #[clap(long, global = true, arg_enum, default_value = "text")]
+    output: OutputFormat,
 }
 
 #[derive(Subcommand, Debug)]
 enum Commands {
     /// Mount compressed package
-    mount {
+    mount(MountOptions),
+    /// Mount all archives listed in a manifest(blocks until unmount-all or Ctrl-C)
+    mountAll {
+        /// Manifest path(TOML, one [[mount]] table per archive)
+        manifestPath: PathBuf,
+        /// Mount config file(default ArchiveMount.toml next to the program, then %APPDATA%\ArchiveMount\config.toml)
+        #[clap(long)]
+        config: Option<PathBuf>,
+    },
+    /// Unmount all archives listed in a manifest
+    unmountAll {
+        /// Manifest path
+        manifestPath: PathBuf,
+    },
+    /// Run a program from an archive, unmount after it exits(exit code of the program)
+    #[clap(trailing_var_arg = true)]
+    run {
         /// Archive path
         archivePath: PathBuf,
-        /// Mount path
-        mountPath: PathBuf,
-        /// Temporary path(default temporary directory)
-        tempPath: Option<PathBuf>,
+        /// Program path in the archive
+        program: PathBuf,
+        /// Program arguments
+        #[clap(multiple_values = true, allow_hyphen_values = true)]
+        args: Vec<String>,
         /// Archive password
         #[clap(short, long)]
         password: Option<String>,
-        /// Threads(0: auto)
-        #[clap(short, long)]
-        #[clap(default_value_t = 0_u16)]
-        threadCount: u16,
-        /// Cache size(unit: MB)
+        /// Wait for child processes started by the program
         #[clap(short, long)]
-        #[clap(default_value_t = 4096)]
-        cacheSize: u64,
-        /// read only mount
-        #[clap(short, long)]
-        readOnly: bool,
-        /// nest mount points
-        #[clap(short, long)]
-        nest: bool,
-        /// After mount open explorer
+        waitChildren: bool,
+        /// Mount config file(default ArchiveMount.toml next to the program, then %APPDATA%\ArchiveMount\config.toml)
+        #[clap(long)]
+        config: Option<PathBuf>,
+        /// Profile in the mount config
+        #[clap(long)]
+        profile: Option<String>,
+    },
+    /// Unmount compressed package
+    unmount {
+        /// mount path
+        mountPath: PathBuf,
+        /// Commit changes to the archive after unmount
         #[clap(short, long)]
-        open: bool,
-        /// Mount volume name
+        commit: bool,
+        /// Archive password(required for an encrypted archive when committing)
         #[clap(short, long)]
-        #[clap(default_value_t = String::from("ArchiveMount"))]
-        volumeName: String,
-        /// Debug mode
+        password: Option<String>,
+    },
+    /// Commit changes of an exited mount to the archive(use unmount --commit for a live mount)
+    commit {
+        /// mount path
+        mountPath: PathBuf,
+        /// Archive password(required for an encrypted archive)
         #[clap(short, long)]
-        debug: bool,
+        password: Option<String>,
     },
-    /// Unmount compressed package
-    unmount {
+    /// Manage snapshots of an overlay
+    snapshot {
+        #[clap(subcommand)]
+        command: SnapshotCommands,
+    },
+    /// List active mounts
+    list {
+        /// Output as JSON
+        #[clap(long)]
+        json: bool,
+    },
+    /// Mount manager hosting multiple mounts
+    manager {
+        #[clap(subcommand)]
+        command: ManagerCommands,
+    },
+    /// Export changes of a mounted archive as a patch archive
+    exportChanges {
         /// mount path
         mountPath: PathBuf,
-        // commit to Archive
-        // #[clap(short, long)]
-        // commit: bool,
+        /// Patch archive path(format by extension)
+        patchPath: PathBuf,
+        /// Patch archive password
+        #[clap(short, long)]
+        password: Option<String>,
     },
+    /// Remove caches, mount points and mount records left by exited processes
+    cleanup {},
 }
 
+#[derive(Subcommand, Debug)]
+enum SnapshotCommands {
+    /// Snapshot the current overlay state
+    create {
+        /// Mount path or overlay directory
+        path: PathBuf,
+        /// Snapshot name
+        name: String,
+    },
+    /// List snapshots
+    list {
+        /// Mount path or overlay directory
+        path: PathBuf,
+    },
+    /// Roll the overlay back to a snapshot(pristine state if no snapshot is specified)
+    rollback {
+        /// Mount path or overlay directory
+        path: PathBuf,
+        /// Snapshot name
+        name: Option<String>,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum ManagerCommands {
+    /// Start the manager(blocks until stopped)
+    start {},
+    /// Stop the manager and unmount all archives it hosts
+    stop {},
+    /// Show the archives mounted by the manager
+    status {},
+    /// Show statistics of a mounted archive
+    stats {
+        /// mount path
+        mountPath: PathBuf,
+    },
+}
 
 pub fn cli() {
-    let cli: Cli = Cli::parse();
+    let matches = Cli::command().get_matches();
+    let cli = Cli::from_arg_matches(&matches).unwrap_or_else(|err| err.exit());
+    setOutputFormat(cli.output);
+    // 清理已退出(如崩溃)的挂载进程遗留的挂载，cleanup 命令输出清理结果
+    if !matches!(cli.command, Commands::cleanup {}) {
+        cleanupStaleMounts();
+    }
     // 处理CLI
     match &cli.command {
         // 需要实现所有的子命令
-        Commands::mount { archivePath, mountPath, tempPath, password, threadCount, cacheSize, readOnly, nest, open, volumeName, debug } => {
-            writeConsole(ConsoleType::Info, &*format!("Mounting archive: {}", archivePath.to_str().unwrap()));
-            if dokan::driver_version() == 0 {
-                writeConsole(ConsoleType::Err, "driver not installed, Please make sure you install the Dokan driver");
+        Commands::mount(options) => {
+            // 应用挂载配置(命令行中指定的参数优先)
+            let mut options = options.clone();
+            let mountMatches = matches.subcommand_matches("mount").unwrap();
+            if let Err(err) = options.resolveStackedPaths() {
+                writeConsole(ConsoleType::Err, &err.to_string());
+                return;
+            }
+            // 缓存目录在整理位置参数后确定
+            let hasTempPath = options.tempPath.is_some();
+            if let Err(err) = options.applyConfig(|id| if id == "tempPath" { hasTempPath } else { mountMatches.occurrences_of(id) > 0 }) {
+                writeConsole(ConsoleType::Err, &err.to_string());
                 return;
             }
-            // 处理相对目录
-            let mut archivePath = archivePath.clone();
-            if archivePath.is_relative() {
-                archivePath = env::current_dir().unwrap().join(archivePath);
+            // 挂载并阻塞至卸载
+            if let MountResult::Local(handle) = mountArchive(options) {
+                handle.wait().ok();
             }
-            if !archivePath.exists() {
-                writeConsole(ConsoleType::Err, "The archive does not exist, if the path contains spaces please use quotation marks.");
+        }
+        Commands::mountAll { manifestPath, config } => {
+            let manifest = match MountManifest::load(manifestPath) {
+                Ok(manifest) => manifest,
+                Err(err) => {
+                    writeConsole(ConsoleType::Err, &*format!("Read manifest failed: {}", err));
+                    return;
+                }
+            };
+            // 逐项挂载，失败的项不影响其他项
+            let mut handleList = Vec::new();
+            let mut mountedCount = 0;
+            for entry in manifest.mountList.iter() {
+                let options = match MountOptions::fromManifestEntry(entry, config.as_deref()) {
+                    Ok(options) => options,
+                    Err(err) => {
+                        writeConsole(ConsoleType::Err, &*format!("{}: {}", entry.archivePath.display(), err));
+                        continue;
+                    }
+                };
+                match mountArchive(options) {
+                    MountResult::Local(handle) => handleList.push(handle),
+                    MountResult::Managed => mountedCount += 1,
+                    MountResult::Failed => {}
+                }
+            }
+            mountedCount += handleList.len();
+            let message = format!("Mounted {} of {} archives", mountedCount, manifest.mountList.len());
+            writeConsole(if mountedCount == manifest.mountList.len() { ConsoleType::Success } else { ConsoleType::Warning }, &message);
+            if handleList.is_empty() {
                 return;
             }
-
-            // 处理挂载路径
-            let mut mountPath = mountPath.clone();
-            if mountPath.is_dir() {
-                if mountPath.metadata().unwrap().len() != 0 {
-                    // 挂载路径为目录则需 1.目录存在 2.不能在挂载前打开 3.目录为空目录
-                    writeConsole(ConsoleType::Err, "The mount path is not empty, please specify an empty directory");
+            // 阻塞至全部卸载(unmount-all 或 Ctrl-C)
+            writeConsole(ConsoleType::Info, "Press Ctrl-C or run unmount-all to unmount all archives");
+            for handle in handleList {
+                handle.wait().ok();
+            }
+        }
+        Commands::unmountAll { manifestPath } => {
+            let manifest = match MountManifest::load(manifestPath) {
+                Ok(manifest) => manifest,
+                Err(err) => {
+                    writeConsole(ConsoleType::Err, &*format!("Read manifest failed: {}", err));
                     return;
                 }
-                // 尝试创建目录
-                fs::create_dir_all(&mountPath).ok();
+            };
+            let failedCount = manifest.mountList.iter().filter(|entry| !unmountArchive(&entry.mountPath)).count();
+            if failedCount > 0 {
+                writeConsole(ConsoleType::Err, &*format!("{} of {} archives failed to unmount", failedCount, manifest.mountList.len()));
+                return;
             }
+            writeConsole(ConsoleType::Success, "unmount successfully");
+        }
+        Commands::run { archivePath, program, args, password, waitChildren, config, profile } => {
+            // 挂载至临时挂载目录(不使用管理进程，挂载随程序退出卸载)
+            let mountPath = TEMP_PATH.join("MountPoint").join(format!("run-{}", process::id()));
+            if let Err(err) = fs::create_dir_all(&mountPath) {
+                writeConsole(ConsoleType::Err, &*format!("Create mount point failed: {}", err));
+                return;
+            }
+            let mut options = MountOptions::new(archivePath, &mountPath);
+            options.password = password.clone();
+            options.config = config.clone();
+            options.profile = profile.clone();
+            let runMatches = matches.subcommand_matches("run").unwrap();
+            if let Err(err) = options.applyConfig(|id| runMatches.occurrences_of(id) > 0) {
+                writeConsole(ConsoleType::Err, &err.to_string());
+                return;
+            }
+            options.nest = false;
+            options.open = false;
+            let handle = match MountBuilder::fromOptions(options).mount() {
+                Ok(handle) => handle,
+                Err(err) => {
+                    writeMountError(&err);
+                    fs::remove_dir_all(&mountPath).ok();
+                    return;
+                }
+            };
 
-            if *nest {
-                mountPath = if mountPath.is_dir() {
-                    mountPath.join(archivePath.file_name().unwrap())
-                } else {
-                    let mountParent = TEMP_PATH.join("MountPoint");
-                    let mountPint = mountParent.join(archivePath.file_name().unwrap());
-                    if fs::create_dir_all(&mountPint).is_err() || !createVirtualDrive(&*mountParent, &*mountPath) {
-                        writeConsole(ConsoleType::Err, "Create virtual mount point failed, please try to unnested mount point");
+            // 运行程序，退出后卸载(卸载时清理挂载目录及缓存)
+            writeConsole(ConsoleType::Progress, &*format!("Running program: {}", program.display()));
+            let result = runProgram(&handle.mountPath().join(program), args, *waitChildren);
+            if handle.unmount().is_err() {
+                writeConsole(ConsoleType::Warning, "unmount failed");
+            }
+            match result {
+                Ok(code) => {
+                    writeConsole(ConsoleType::Info, &*format!("The program exited with code {}", code));
+                    setExitCode(code);
+                }
+                Err(err) => writeConsole(ConsoleType::Err, &*format!("Run program failed: {}", err)),
+            }
+        }
+        Commands::unmount { mountPath, commit, password } => {
+            // 提交修改时挂载进程卸载后保留覆盖层，卸载完成后再提交
+            let commitRecord = if *commit {
+                match getCommitRecord(mountPath, true) {
+                    Ok(mountRecord) => mountRecord,
+                    Err(err) => {
+                        writeConsole(ConsoleType::Err, &*format!("commit failed: {}", err));
                         return;
-                    };
-                    mountPint
-                    // mountPath.join(archivePath.file_name().unwrap())
+                    }
+                }
+            } else {
+                None
+            };
+            if let Some(Err(err)) = commitRecord.as_ref().map(|mountRecord| mountRecord.requestCommit()) {
+                writeConsole(ConsoleType::Err, &*format!("commit failed: {}", err));
+                return;
+            }
+            if !unmountArchive(mountPath) {
+                if let Some(mountRecord) = &commitRecord {
+                    mountRecord.cancelCommit();
+                }
+                writeConsole(ConsoleType::Err, "unmount failed");
+                return;
+            }
+            writeConsole(ConsoleType::Success, "unmount successfully");
+            if let Some(mountRecord) = commitRecord {
+                let committed = if mountRecord.waitReleased(UNMOUNT_TIMEOUT) {
+                    commitOverlay(&mountRecord, password.as_deref())
+                } else {
+                    writeConsole(ConsoleType::Err, "commit failed: The archive was not unmounted in time");
+                    false
                 };
+                mountRecord.cancelCommit();
+                if committed {
+                    if !mountRecord.persistentOverlay {
+                        fs::remove_dir_all(&mountRecord.overlayPath).ok();
+                    }
+                    writeConsole(ConsoleType::Success, "commit successfully");
+                } else if !mountRecord.persistentOverlay {
+                    writeConsole(ConsoleType::Warning, &*format!("The changes are kept in: {}", mountRecord.overlayPath.display()));
+                }
+            }
+        }
+        Commands::commit { mountPath, password } => {
+            match getCommitRecord(mountPath, false) {
+                Ok(Some(mountRecord)) => {
+                    if commitOverlay(&mountRecord, password.as_deref()) {
+                        writeConsole(ConsoleType::Success, "commit successfully");
+                    }
+                }
+                Ok(None) => {}
+                Err(err) => writeConsole(ConsoleType::Err, &*format!("commit failed: {}", err)),
+            }
+        }
+        Commands::snapshot { command } => {
+            let path = match command {
+                SnapshotCommands::create { path, .. } | SnapshotCommands::list { path } | SnapshotCommands::rollback { path, .. } => path,
+            };
+            let overlay = match getOverlayPath(path).map(|overlayPath| Overlay::new(&overlayPath)) {
+                Some(Ok(overlay)) => overlay,
+                _ => {
+                    writeConsole(ConsoleType::Err, "No archive is mounted at the path and it is not an overlay directory");
+                    return;
+                }
+            };
+            match command {
+                SnapshotCommands::create { path: _, name } => {
+                    if let Err(err) = overlay.createSnapshot(name) {
+                        writeConsole(ConsoleType::Err, &*format!("create snapshot failed: {}", err));
+                        return;
+                    }
+                    writeConsole(ConsoleType::Success, &*format!("snapshot created: {}", name));
+                }
+                SnapshotCommands::list { path: _ } => {
+                    for (name, created) in overlay.listSnapshots() {
+                        let created: DateTime<Local> = created.into();
+                        writeConsole(ConsoleType::Info, &*format!("{}  {}", created.format("%Y-%m-%d %H:%M:%S"), name));
+                    }
+                    writeConsole(ConsoleType::Success, "list snapshots successfully");
+                }
+                SnapshotCommands::rollback { path: _, name } => {
+                    if let Err(err) = overlay.rollback(name.as_deref()) {
+                        writeConsole(ConsoleType::Err, &*format!("rollback failed: {}", err));
+                        return;
+                    }
+                    writeConsole(ConsoleType::Success, &*format!("rollback to {} successfully", name.as_deref().unwrap_or("pristine state")));
+                }
+            }
+        }
+        Commands::list { json } => {
+            let statusList = listMountStatus();
+            if *json || isJsonOutput() {
+                println!("{}", serde_json::to_string_pretty(&statusList).unwrap());
+                return;
+            }
+            if statusList.is_empty() {
+                writeConsole(ConsoleType::Info, "No archive is mounted");
+                return;
+            }
+            printMountTable(&statusList);
+        }
+        Commands::manager { command } => {
+            if let ManagerCommands::start {} = command {
+                if let Err(err) = Manager::run() {
+                    writeConsole(ConsoleType::Err, &*format!("Start manager failed: {}", err));
+                }
+                return;
+            }
+            let request = match command {
+                ManagerCommands::stop {} => ManagerRequest::stop,
+                ManagerCommands::stats { mountPath } => ManagerRequest::stats { mountPath: mountPath.clone() },
+                _ => ManagerRequest::status,
+            };
+            let response = match sendRequest(&request) {
+                Ok(response) => response,
+                Err(err) => {
+                    writeConsole(ConsoleType::Err, &err.to_string());
+                    return;
+                }
+            };
+            if !response.success {
+                writeConsole(ConsoleType::Err, &response.message);
+                return;
+            }
+            for mount in response.mounts.iter() {
+                writeConsole(ConsoleType::Info, &*format!("{} -> {}", mount.record.archivePath.display(), mount.record.mountPath.display()));
+                if let ManagerCommands::stats { .. } = command {
+                    writeConsole(ConsoleType::Info, &*format!("open handles: {}, cache usage: {}, read: {}, written: {}",
+                        mount.openHandles.unwrap_or(0), formatSize(mount.cacheUsage), formatSize(mount.readBytes.unwrap_or(0)), formatSize(mount.writeBytes.unwrap_or(0))));
+                }
+            }
+            writeConsole(ConsoleType::Success, if response.message.is_empty() { "request successfully" } else { &response.message });
+        }
+        Commands::exportChanges { mountPath, patchPath, password } => {
+            let mountRecord = match findMountRecord(mountPath) {
+                Some(mountRecord) => mountRecord,
+                None => {
+                    writeConsole(ConsoleType::Err, "No archive is mounted at the mount path");
+                    return;
+                }
+            };
+            let mut patchPath = patchPath.clone();
+            if patchPath.is_relative() {
+                patchPath = env::current_dir().unwrap().join(patchPath);
             }
 
-            // 处理缓存目录
-            let extractPath = if let Some(tempPath) = tempPath { tempPath.clone() } else { TEMP_PATH.join("ArchiveTemp") }.join(&archivePath.file_name().unwrap());
-            let password = password.as_ref().map(|password| password.as_str());
-
-            // 读取压缩包信息
-            writeConsole(ConsoleType::Info, "Reading archive list......");
-            let archiveFileInfoList = sevenZip::new().unwrap().listArchiveFiles(&*archivePath, password).unwrap();
-            if archiveFileInfoList.is_empty() {
-                writeConsole(ConsoleType::Err, "The Archive information is not detected, please confirm it is the correct archive or encrypted archive");
+            writeConsole(ConsoleType::Progress, &*format!("Exporting changes to patch archive: {}", patchPath.to_str().unwrap()));
+            let result = Overlay::new(&mountRecord.overlayPath).and_then(|overlay| overlay.exportChanges(&sevenZip::new().unwrap(), &patchPath, password.as_deref()));
+            if let Err(err) = result {
+                writeConsole(ConsoleType::Err, &*format!("export failed: {}", err));
                 return;
             }
+            writeConsole(ConsoleType::Success, "export successfully");
+        }
+        Commands::cleanup {} => {
+            for record in cleanupStaleMounts() {
+                writeConsole(ConsoleType::Info, &*format!("Removed stale mount: {} ({})", record.mountPath.display(), record.archivePath.display()));
+            }
+            for path in removeOrphanedPaths() {
+                writeConsole(ConsoleType::Info, &*format!("Removed orphaned directory: {}", path.display()));
+            }
+            writeConsole(ConsoleType::Success, "cleanup successfully");
+        }
+    }
+}
 
-            // 开始挂载
-            let archiveFS = ArchiveFS::ArchiveFS::new(&*archivePath, &extractPath, password, *cacheSize, *readOnly, archiveFileInfoList, *open, volumeName, *debug);
-            let result = archiveFS.mount(&*mountPath, *threadCount);
+/// 挂载结果
+enum MountResult {
+    /// 由当前进程挂载
+    Local(MountHandle),
+    /// 由管理进程挂载
+    Managed,
+    /// 挂载失败(已输出错误)
+    Failed,
+}
 
-            // 处理错误信息
-            if let Err(err) = result {
-                match err {
-                    MountError::Error => { writeConsole(ConsoleType::Err, "An error occurred"); }
-                    MountError::DriveLetterError => { writeConsole(ConsoleType::Err, "Drive letter error"); }
-                    MountError::DriverInstallError => { writeConsole(ConsoleType::Err, "Can't install the Dokan driver"); }
-                    MountError::StartError => { writeConsole(ConsoleType::Err, "The driver responds that something is wrong"); }
-                    MountError::MountError => { writeConsole(ConsoleType::Err, "Can't assign a drive letter or mount point"); }
-                    MountError::MountPointError => { writeConsole(ConsoleType::Err, "The mount point is invalid"); }
-                    MountError::VersionError => { writeConsole(ConsoleType::Err, "The Dokan version that this wrapper is targeting is incompatible with the loaded Dokan library"); }
-                }
+/// 挂载压缩包(管理进程运行时由管理进程挂载)
+fn mountArchive(mut options: MountOptions) -> MountResult {
+    if isManagerRunning() {
+        options.absolutize();
+        let mountPath = options.mountPath.to_str().unwrap().to_string();
+        match sendRequest(&ManagerRequest::mount { options }) {
+            Ok(response) if response.success => {
+                writeEvent(ConsoleEvent::mounted { mountPath, message: response.message });
+                return MountResult::Managed;
             }
+            Ok(response) => writeConsole(ConsoleType::Err, &response.message),
+            Err(err) => writeConsole(ConsoleType::Err, &*format!("Send request to the manager failed: {}", err)),
         }
-        Commands::unmount { mountPath } => {
-            if !ArchiveFS::ArchiveFS::unmount(mountPath) {
-                writeConsole(ConsoleType::Err, "unmount failed");
-                return;
+        return MountResult::Failed;
+    }
+
+    match MountBuilder::fromOptions(options).mount() {
+        Ok(handle) => MountResult::Local(handle),
+        Err(err) => {
+            writeMountError(&err);
+            MountResult::Failed
+        }
+    }
+}
+
+/// 输出挂载错误(挂载错误使用对应的退出码)
+fn writeMountError(err: &anyhow::Error) {
+    match err.downcast_ref::<MountFailed>() {
+        Some(mountFailed) => writeError(mountFailed.code(), &mountFailed.to_string()),
+        None => writeConsole(ConsoleType::Err, &err.to_string()),
+    }
+}
+
+/// 卸载压缩包(由管理进程托管的挂载通过管理进程卸载)
+fn unmountArchive(mountPath: &Path) -> bool {
+    if isManagerRunning() {
+        if let Ok(response) = sendRequest(&ManagerRequest::unmount { mountPath: mountPath.to_path_buf() }) {
+            if response.success {
+                return true;
             }
-            writeConsole(ConsoleType::Success, "unmount successfully");
         }
     }
+    ArchiveFS::ArchiveFS::unmount(mountPath)
+}
+
+/// 以表格输出挂载列表
+fn printMountTable(statusList: &[MountStatus]) {
+    let header = ["MOUNT", "ARCHIVE", "CACHE", "CACHE USAGE", "MODE", "UPTIME", "HANDLES"];
+    let rowList: Vec<[String; 7]> = statusList.iter().map(|status| [
+        status.record.mountPath.display().to_string(),
+        status.record.archivePath.display().to_string(),
+        status.record.extractPath.display().to_string(),
+        formatSize(status.cacheUsage),
+        if status.record.readOnly { "read-only" } else if status.record.persistentOverlay { "persistent" } else { "temporary" }.to_string(),
+        formatDuration(status.uptime),
+        status.openHandles.map(|openHandles| openHandles.to_string()).unwrap_or_else(|| "-".to_string()),
+    ]).collect();
+
+    // 列宽取表头与内容的最大宽度
+    let mut widthList = header.map(|title| title.len());
+    for row in rowList.iter() {
+        for (index, cell) in row.iter().enumerate() {
+            widthList[index] = widthList[index].max(cell.chars().count());
+        }
+    }
+    let formatRow = |row: Vec<&str>| row.iter().enumerate()
+        .map(|(index, cell)| format!("{:width$}", cell, width = widthList[index]))
+        .collect::<Vec<String>>()
+        .join("  ")
+        .trim_end()
+        .to_string();
+    println!("{}", formatRow(header.to_vec()));
+    for row in rowList.iter() {
+        println!("{}", formatRow(row.iter().map(|cell| cell.as_str()).collect()));
+    }
+}
+
+/// 格式化大小
+fn formatSize(size: u64) -> String {
+    match size {
+        size if size >= 1024 * 1024 * 1024 => format!("{:.1} GB", size as f64 / 1024.0 / 1024.0 / 1024.0),
+        size if size >= 1024 * 1024 => format!("{:.1} MB", size as f64 / 1024.0 / 1024.0),
+        size if size >= 1024 => format!("{:.1} KB", size as f64 / 1024.0),
+        size => format!("{} B", size),
+    }
+}
+
+/// 格式化时长
+fn formatDuration(seconds: u64) -> String {
+    if seconds >= 86400 {
+        format!("{}d {}h", seconds / 86400, seconds % 86400 / 3600)
+    } else if seconds >= 3600 {
+        format!("{}h {}m", seconds / 3600, seconds % 3600 / 60)
+    } else if seconds >= 60 {
+        format!("{}m {}s", seconds / 60, seconds % 60)
+    } else {
+        format!("{}s", seconds)
+    }
+}
+
+/// 获取覆盖层目录(挂载路径使用挂载记录中的覆盖层目录，否则视为覆盖层目录)
+fn getOverlayPath(path: &Path) -> Option<PathBuf> {
+    if let Some(mountRecord) = findMountRecord(path) {
+        return Some(mountRecord.overlayPath);
+    }
+    let mut path = path.to_path_buf();
+    if path.is_relative() {
+        path = env::current_dir().unwrap().join(path);
+    }
+    if path.join("upper").is_dir() { Some(path) } else { None }
+}
+
+/// 获取要提交修改的挂载记录，只读挂载时返回 None
+/// 参数2: 是否在卸载后提交(否则挂载进程需已退出，避免替换正在使用的压缩包)
+fn getCommitRecord(mountPath: &Path, unmount: bool) -> anyhow::Result<Option<MountRecord>> {
+    let mountRecord = match findMountRecord(mountPath) {
+        Some(mountRecord) => mountRecord,
+        None => anyhow::bail!("No archive is mounted at the mount path"),
+    };
+    if mountRecord.readOnly {
+        writeConsole(ConsoleType::Warning, "The archive is mounted read only, nothing to commit");
+        return Ok(None);
+    }
+    if !mountRecord.commitSupported {
+        anyhow::bail!("Committing is not supported for directory mounts, expanded nested archives or multi-volume archives");
+    }
+    if !unmount && !mountRecord.isStale() {
+        anyhow::bail!("The archive is still mounted, please use unmount --commit to commit the changes");
+    }
+    Ok(Some(mountRecord))
+}
+
+/// 提交覆盖层的修改至压缩包(密码仅由参数指定)
+fn commitOverlay(mountRecord: &MountRecord, password: Option<&str>) -> bool {
+    writeConsole(ConsoleType::Progress, &*format!("Committing changes to archive: {}", mountRecord.archivePath.to_str().unwrap()));
+    let result = Overlay::new(&mountRecord.overlayPath).and_then(|overlay| overlay.commit(&sevenZip::new().unwrap(), &mountRecord.archivePath, password));
+    if let Err(err) = result {
+        writeConsole(ConsoleType::Err, &*format!("commit failed: {}", err));
+        return false;
+    }
+    true
 }
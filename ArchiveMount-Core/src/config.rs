@@ -0,0 +1,214 @@
+use std::collections::BTreeMap;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, bail, Result};
+use serde::Deserialize;
+
+use crate::ArchiveFS::ArchiveChangeMode;
+use crate::writeRule::PathPattern;
+
+/// 挂载配置(全局默认值及命名配置)
+///
+/// ```toml
+/// [default]
+/// cacheSize = 2048
+///
+/// [profiles.game]
+/// match = "D:/Games/*.7z"
+/// readOnly = true
+/// passwordEnv = "GAME_PASSWORD"
+/// prefetch = ["*.exe", "*.dll"]
+/// ```
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MountConfig {
+    /// 全局默认值
+    pub default: MountProfile,
+    /// 命名配置
+    pub profiles: BTreeMap<String, MountProfile>,
+}
+
+/// 挂载配置项(未设置的项使用命令行默认值)
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MountProfile {
+    /// 压缩包路径匹配模式(匹配时自动使用该配置)
+    #[serde(rename = "match")]
+    pub matchPattern: Option<String>,
+    /// 缓存目录
+    pub tempPath: Option<PathBuf>,
+    /// 缓存大小(单位: MB)
+    pub cacheSize: Option<u64>,
+    /// 线程数(0为自动)
+    pub threadCount: Option<u16>,
+    pub readOnly: Option<bool>,
+    pub nest: Option<bool>,
+    pub open: Option<bool>,
+    pub volumeName: Option<String>,
+    pub patch: Option<Vec<PathBuf>>,
+    /// 持久覆盖层目录
+    pub overlay: Option<PathBuf>,
+    /// 写入规则文件
+    pub rules: Option<PathBuf>,
+    /// 配置目录(persist 规则)
+    pub profileDir: Option<PathBuf>,
+    pub sevenZip: Option<PathBuf>,
+    /// 密码来源: 明文、环境变量、文件(取第一行)
+    pub password: Option<String>,
+    pub passwordEnv: Option<String>,
+    pub passwordFile: Option<PathBuf>,
+    /// 空闲自动卸载的时长(如 30s、10m、1h)
+    pub idleTimeout: Option<String>,
+    /// 挂载后预先解压的文件(glob 模式)
+    pub prefetch: Option<Vec<String>>,
+    /// 仅显示匹配的条目(glob 模式)
+    pub include: Option<Vec<String>>,
+    /// 隐藏匹配的条目(glob 模式)
+    pub exclude: Option<Vec<String>>,
+    /// 压缩包变化时的处理方式(reload、refuse、ignore)
+    pub onArchiveChange: Option<ArchiveChangeMode>,
+    /// 卸载时提交覆盖层的修改至压缩包
+    pub commitOnUnmount: Option<bool>,
+    /// 挂载压缩包目录时子目录名去除扩展名
+    pub stripExtension: Option<bool>,
+    /// 展开嵌套压缩包的最大层数
+    pub nestedDepth: Option<u32>,
+    /// 挂载根目录(压缩包中的目录)
+    pub root: Option<String>,
+    /// 去除的顶层目录层数
+    pub stripComponents: Option<u32>,
+}
+
+impl MountProfile {
+    /// 合并配置(参数中已设置的项覆盖当前项)
+    pub fn merge(&mut self, other: &MountProfile) {
+        macro_rules! mergeFields {
+            ($($field:ident),*) => {
+                $(if other.$field.is_some() { self.$field = other.$field.clone(); })*
+            };
+        }
+        mergeFields!(tempPath, cacheSize, threadCount, readOnly, nest, open, volumeName, patch, overlay, rules, profileDir, sevenZip, idleTimeout, prefetch, include, exclude, onArchiveChange, commitOnUnmount, stripExtension, nestedDepth, root, stripComponents);
+        // 密码来源互斥，以后设置的为准
+        if other.password.is_some() || other.passwordEnv.is_some() || other.passwordFile.is_some() {
+            self.password = other.password.clone();
+            self.passwordEnv = other.passwordEnv.clone();
+            self.passwordFile = other.passwordFile.clone();
+        }
+    }
+
+    /// 读取密码
+    pub fn getPassword(&self) -> Result<Option<String>> {
+        if let Some(password) = &self.password {
+            return Ok(Some(password.clone()));
+        }
+        if let Some(name) = &self.passwordEnv {
+            return match env::var(name) {
+                Ok(password) => Ok(Some(password)),
+                Err(_) => bail!("The password environment variable is not set: {}", name),
+            };
+        }
+        if let Some(passwordFile) = &self.passwordFile {
+            let content = fs::read_to_string(passwordFile).map_err(|err| anyhow!("Read password file failed: {}", err))?;
+            return Ok(Some(content.lines().next().unwrap_or("").to_string()));
+        }
+        Ok(None)
+    }
+}
+
+impl MountConfig {
+    /// 查找默认配置文件，依次为程序目录下的 ArchiveMount.toml、%APPDATA%\ArchiveMount\config.toml(均不存在时返回 None)
+    pub fn findDefaultPath() -> Option<PathBuf> {
+        let exePath = env::current_exe().ok().map(|exePath| exePath.with_file_name("ArchiveMount.toml"));
+        let appDataPath = env::var_os("APPDATA").map(|appData| PathBuf::from(appData).join("ArchiveMount").join("config.toml"));
+        exePath.into_iter().chain(appDataPath).find(|path| path.exists())
+    }
+
+    /// 读取配置文件
+    pub fn load(path: &Path) -> Result<MountConfig> {
+        let content = fs::read_to_string(path)?;
+        MountConfig::parse(&content)
+    }
+
+    /// 解析配置
+    pub fn parse(content: &str) -> Result<MountConfig> {
+        let config: MountConfig = toml::from_str(content)?;
+        for (name, profile) in config.profiles.iter() {
+            if let Some(pattern) = &profile.matchPattern {
+                PathPattern::new(pattern).map_err(|err| anyhow!("Invalid match pattern of profile {}: {}", name, err))?;
+            }
+        }
+        Ok(config)
+    }
+
+    /// 获取压缩包使用的配置: 全局默认值 < 匹配压缩包路径的配置 < 指定的配置
+    pub fn resolve(&self, archivePath: &Path, profileName: Option<&str>) -> Result<MountProfile> {
+        let mut profile = self.default.clone();
+        let archivePath = archivePath.to_str().unwrap_or("");
+        for item in self.profiles.values() {
+            let pattern = item.matchPattern.as_deref().and_then(|pattern| PathPattern::new(pattern).ok());
+            if pattern.map(|pattern| pattern.isMatch(archivePath)).unwrap_or(false) {
+                profile.merge(item);
+            }
+        }
+        if let Some(profileName) = profileName {
+            match self.profiles.get(profileName) {
+                Some(item) => profile.merge(item),
+                None => bail!("The profile does not exist in the config: {}", profileName),
+            }
+        }
+        Ok(profile)
+    }
+}
+
+/// 挂载清单(mount-all 一次挂载多个压缩包)
+///
+/// ```toml
+/// [[mount]]
+/// archivePath = 'D:\Game.7z'
+/// mountPath = 'Z:'
+/// profile = "game"
+/// readOnly = true
+/// ```
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MountManifest {
+    #[serde(rename = "mount")]
+    pub mountList: Vec<ManifestEntry>,
+}
+
+/// 挂载清单项(配置项优先于挂载配置)
+#[derive(Debug, Clone, Deserialize)]
+pub struct ManifestEntry {
+    /// 压缩包路径(相对路径基于清单所在目录)
+    pub archivePath: PathBuf,
+    /// 挂载路径
+    pub mountPath: PathBuf,
+    /// 挂载配置中的配置名
+    pub profile: Option<String>,
+    #[serde(flatten)]
+    pub options: MountProfile,
+}
+
+impl MountManifest {
+    /// 读取挂载清单
+    pub fn load(path: &Path) -> Result<MountManifest> {
+        let mut manifest = MountManifest::parse(&fs::read_to_string(path)?)?;
+        if let Some(parent) = path.parent() {
+            for entry in manifest.mountList.iter_mut().filter(|entry| entry.archivePath.is_relative()) {
+                entry.archivePath = parent.join(&entry.archivePath);
+            }
+        }
+        Ok(manifest)
+    }
+
+    /// 解析挂载清单
+    pub fn parse(content: &str) -> Result<MountManifest> {
+        let manifest: MountManifest = toml::from_str(content)?;
+        if manifest.mountList.is_empty() {
+            bail!("The manifest does not contain any mount");
+        }
+        Ok(manifest)
+    }
+}
@@ -0,0 +1,87 @@
+use std::collections::HashSet;
+
+use anyhow::{anyhow, Result};
+
+use crate::ArchiveFS::stripRootPath;
+use crate::sevenZip::ArchiveFileInfo;
+use crate::writeRule::PathPattern;
+
+/// 默认隐藏的条目(回收站、卷标目录、资源管理器生成的配置文件)
+const DEFAULT_EXCLUDE_LIST: [&str; 3] = ["desktop.ini", "$RECYCLE.BIN", "System Volume Information"];
+
+/// 条目过滤规则: 隐藏压缩包中匹配的条目
+/// - 匹配排除模式的条目(目录时包括其子项)不显示
+/// - 指定包含模式时仅显示匹配的条目(目录时包括其子项)及其上级目录
+/// - 匹配模式不区分大小写，不含 / 时匹配任意目录下的文件名
+/// - 匹配挂载中的路径(去除挂载根目录)，挂载根目录之外的条目不过滤
+/// - 仅隐藏压缩包中的条目，程序可创建匹配的文件(写入上层，如 *.tmp 临时文件)；默认隐藏的条目始终不可访问
+#[derive(Debug, Clone)]
+pub struct EntryFilter {
+    includeList: Vec<PathPattern>,
+    excludeList: Vec<PathPattern>,
+    /// 挂载根目录(压缩包中的路径，为空时挂载整个压缩包)
+    rootPath: String,
+}
+
+impl Default for EntryFilter {
+    fn default() -> EntryFilter {
+        EntryFilter::new(&[], &[]).unwrap()
+    }
+}
+
+impl EntryFilter {
+    /// 参数1: 包含模式
+    /// 参数2: 排除模式(默认隐藏的条目始终排除)
+    pub fn new(includeList: &[String], excludeList: &[String]) -> Result<EntryFilter> {
+        let parse = |pattern: &str| PathPattern::new(pattern.trim_end_matches(|c| c == '/' || c == '\\')).map_err(|err| anyhow!("Invalid filter pattern {}: {}", pattern, err));
+        Ok(EntryFilter {
+            includeList: includeList.iter().map(|pattern| parse(pattern)).collect::<Result<_>>()?,
+            excludeList: DEFAULT_EXCLUDE_LIST.iter().map(|pattern| parse(pattern)).chain(excludeList.iter().map(|pattern| parse(pattern))).collect::<Result<_>>()?,
+            rootPath: String::new(),
+        })
+    }
+
+    /// 设置挂载根目录
+    pub(crate) fn setRootPath(&mut self, rootPath: &str) {
+        self.rootPath = rootPath.to_string();
+    }
+
+    /// 挂载中的路径(或其上级目录)是否被排除
+    pub fn isExcluded(&self, relativePath: &str) -> bool {
+        getSelfAndParents(relativePath).any(|path| self.excludeList.iter().any(|pattern| pattern.isMatch(path)))
+    }
+
+    /// 路径(或其上级目录)是否为默认隐藏的条目(不可打开或创建)
+    pub fn isSystemEntry(&self, relativePath: &str) -> bool {
+        let systemList = &self.excludeList[..DEFAULT_EXCLUDE_LIST.len()];
+        getSelfAndParents(relativePath).any(|path| systemList.iter().any(|pattern| pattern.isMatch(path)))
+    }
+
+    /// 挂载中的路径(或其上级目录)是否匹配包含模式(未指定包含模式时均匹配)
+    fn isIncluded(&self, relativePath: &str) -> bool {
+        self.includeList.is_empty() || getSelfAndParents(relativePath).any(|path| self.includeList.iter().any(|pattern| pattern.isMatch(path)))
+    }
+
+    /// 过滤文件列表
+    /// 参数2: 保留的目录(尚未读取文件列表的压缩包子目录)
+    pub fn apply(&self, fileList: Vec<ArchiveFileInfo>, keepList: &[String]) -> Vec<ArchiveFileInfo> {
+        let isExcluded = |item: &ArchiveFileInfo| matches!(stripRootPath(&self.rootPath, &item.Path), Some(path) if self.isExcluded(path));
+        let isIncluded = |item: &ArchiveFileInfo| !matches!(stripRootPath(&self.rootPath, &item.Path), Some(path) if !self.isIncluded(path));
+        let mut fileList: Vec<ArchiveFileInfo> = fileList.into_iter().filter(|item| !isExcluded(item)).collect();
+        if self.includeList.is_empty() {
+            return fileList;
+        }
+        // 包含的条目的上级目录
+        let mut parentSet: HashSet<String> = keepList.iter().flat_map(|path| getSelfAndParents(path)).map(|path| path.to_lowercase()).collect();
+        for item in fileList.iter().filter(|item| isIncluded(item)) {
+            parentSet.extend(getSelfAndParents(&item.Path).skip(1).map(|path| path.to_lowercase()));
+        }
+        fileList.retain(|item| isIncluded(item) || (item.is_dir && parentSet.contains(&item.Path.to_lowercase())));
+        fileList
+    }
+}
+
+/// 路径本身及其所有上级目录
+fn getSelfAndParents(relativePath: &str) -> impl Iterator<Item=&str> {
+    std::iter::successors(Some(relativePath), |path| path.rfind('\\').map(|index| &path[..index]))
+}
@@ -0,0 +1,114 @@
+use std::mem;
+use std::os::windows::io::AsRawHandle;
+use std::os::windows::process::CommandExt;
+use std::path::Path;
+use std::process::{Child, Command};
+use std::ptr::null_mut;
+use std::thread;
+use std::time::Duration;
+
+use anyhow::{bail, Result};
+use winapi::shared::minwindef::{DWORD, LPVOID};
+use winapi::um::handleapi::{CloseHandle, INVALID_HANDLE_VALUE};
+use winapi::um::jobapi2::{AssignProcessToJobObject, CreateJobObjectW, QueryInformationJobObject};
+use winapi::um::processthreadsapi::{OpenThread, ResumeThread};
+use winapi::um::tlhelp32::{CreateToolhelp32Snapshot, TH32CS_SNAPTHREAD, Thread32First, Thread32Next, THREADENTRY32};
+use winapi::um::winnt::{HANDLE, JOBOBJECT_BASIC_ACCOUNTING_INFORMATION, JobObjectBasicAccountingInformation, THREAD_SUSPEND_RESUME};
+
+use crate::utils::console::{ConsoleType, EXIT_ERROR, writeConsole};
+
+/// 创建进程后挂起主线程(加入作业后再恢复)
+const CREATE_SUSPENDED: u32 = 0x00000004;
+
+/// 进程作业对象(统计程序及其子进程)
+struct ProcessJob(HANDLE);
+
+impl ProcessJob {
+    fn new() -> Result<ProcessJob> {
+        let handle = unsafe { CreateJobObjectW(null_mut(), null_mut()) };
+        if handle.is_null() {
+            bail!("Create job object failed");
+        }
+        Ok(ProcessJob(handle))
+    }
+
+    /// 将进程加入作业(进程以挂起状态创建，确保其子进程均在作业中)
+    fn assign(&self, processHandle: HANDLE) -> bool {
+        unsafe { AssignProcessToJobObject(self.0, processHandle) != 0 }
+    }
+
+    /// 作业中仍在运行的进程数
+    fn activeProcesses(&self) -> u32 {
+        let mut information: JOBOBJECT_BASIC_ACCOUNTING_INFORMATION = unsafe { mem::zeroed() };
+        let result = unsafe {
+            QueryInformationJobObject(self.0, JobObjectBasicAccountingInformation, &mut information as *mut _ as LPVOID, mem::size_of::<JOBOBJECT_BASIC_ACCOUNTING_INFORMATION>() as DWORD, null_mut())
+        };
+        if result == 0 { 0 } else { information.ActiveProcesses }
+    }
+}
+
+impl Drop for ProcessJob {
+    fn drop(&mut self) {
+        unsafe { CloseHandle(self.0) };
+    }
+}
+
+/// 运行程序并等待退出，返回程序的退出码
+/// 参数1: 程序路径(工作目录为程序所在目录)
+/// 参数3: 是否等待程序创建的子进程全部退出
+pub fn runProgram(programPath: &Path, args: &[String], waitChildren: bool) -> Result<i32> {
+    if !programPath.is_file() {
+        bail!("The program does not exist in the archive: {}", programPath.display());
+    }
+    let job = if waitChildren { Some(ProcessJob::new()?) } else { None };
+    let mut command = Command::new(programPath);
+    command.args(args).current_dir(programPath.parent().unwrap());
+    if job.is_some() {
+        command.creation_flags(CREATE_SUSPENDED);
+    }
+    let mut child = command.spawn()?;
+    let job = job.filter(|job| {
+        let assigned = job.assign(child.as_raw_handle() as HANDLE);
+        if !assigned {
+            writeConsole(ConsoleType::Warning, "Failed to track the child processes of the program, only the program itself is waited for");
+        }
+        assigned
+    });
+    if waitChildren && !resumeProcess(&child) {
+        child.kill().ok();
+        bail!("Failed to resume the program");
+    }
+    let status = child.wait()?;
+
+    // 等待子进程退出(启动器类程序退出后由子进程继续运行)
+    if let Some(job) = job {
+        while job.activeProcesses() > 0 {
+            thread::sleep(Duration::from_millis(500));
+        }
+    }
+    Ok(status.code().unwrap_or(EXIT_ERROR))
+}
+
+/// 恢复挂起创建的进程(恢复其所有线程)
+fn resumeProcess(child: &Child) -> bool {
+    let snapshot = unsafe { CreateToolhelp32Snapshot(TH32CS_SNAPTHREAD, 0) };
+    if snapshot == INVALID_HANDLE_VALUE {
+        return false;
+    }
+    let mut resumed = false;
+    let mut entry: THREADENTRY32 = unsafe { mem::zeroed() };
+    entry.dwSize = mem::size_of::<THREADENTRY32>() as DWORD;
+    let mut found = unsafe { Thread32First(snapshot, &mut entry) } != 0;
+    while found {
+        if entry.th32OwnerProcessID == child.id() {
+            let thread = unsafe { OpenThread(THREAD_SUSPEND_RESUME, 0, entry.th32ThreadID) };
+            if !thread.is_null() {
+                resumed |= unsafe { ResumeThread(thread) } != DWORD::MAX;
+                unsafe { CloseHandle(thread) };
+            }
+        }
+        found = unsafe { Thread32Next(snapshot, &mut entry) } != 0;
+    }
+    unsafe { CloseHandle(snapshot) };
+    resumed
+}
@@ -0,0 +1,65 @@
+//! ArchiveMount 挂载库: 将压缩包挂载为虚拟磁盘或目录
+//!
+//! 使用 [`MountBuilder`] 配置挂载参数，挂载后返回 [`MountHandle`]，可获取运行统计、卸载
+
+// 禁用变量命名警告
+#![allow(non_snake_case)]
+#![allow(non_camel_case_types)]
+// 禁用未使用代码警告
+#![allow(dead_code)]
+
+#[macro_use]
+extern crate lazy_static;
+
+use std::env;
+use std::path::PathBuf;
+
+use rust_embed::RustEmbed;
+
+pub mod ArchiveFS;
+mod archiveLayer;
+pub mod config;
+mod entryFilter;
+pub mod launcher;
+pub mod manager;
+pub mod mountBuilder;
+pub mod mountOptions;
+pub mod overlay;
+pub mod registry;
+pub mod sevenZip;
+pub mod utils;
+mod volume;
+pub mod writeRule;
+
+#[cfg(test)]
+mod tests;
+
+pub use config::{MountConfig, MountManifest, MountProfile};
+pub use manager::MountStatus;
+pub use mountBuilder::{MountBuilder, MountHandle, unmountActiveMounts};
+pub use mountOptions::{MountFailed, MountOptions};
+pub use utils::console::ConsoleEvent;
+
+// 配置内置资源
+// x64平台
+#[cfg(target_arch = "x86_64")]
+#[derive(RustEmbed)]
+#[folder = "./assets-x64"]
+pub struct Asset;
+
+// x86平台
+#[cfg(target_arch = "x86")]
+#[derive(RustEmbed)]
+#[folder = "./assets-x86"]
+pub struct Asset;
+
+// ARM平台
+#[cfg(target_arch = "arm")]
+#[derive(RustEmbed)]
+#[folder = "./assets-ARM64"]
+pub struct Asset;
+
+// 设置静态变量
+lazy_static! {
+    pub static ref TEMP_PATH: PathBuf = env::temp_dir().join("ArchiveMount");
+}
@@ -4,58 +4,30 @@
 // 禁用未使用代码警告
 #![allow(dead_code)]
 
-#[macro_use]
-extern crate lazy_static;
+use std::{fs, process};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
 
-use std::{env, fs, process};
-use std::path::PathBuf;
-use std::sync::Arc;
-use std::sync::atomic::{AtomicUsize, Ordering};
+use winapi::shared::minwindef::{BOOL, DWORD, TRUE};
+use winapi::um::consoleapi::SetConsoleCtrlHandler;
+use winapi::um::wincon::{CTRL_BREAK_EVENT, CTRL_C_EVENT};
 
-use rust_embed::RustEmbed;
+use ArchiveMount::{manager, registry, TEMP_PATH, unmountActiveMounts};
+use ArchiveMount::utils::console::{ConsoleType, getExitCode, writeConsole};
 
-mod ArchiveFS;
-mod sevenZip;
 mod cli;
-mod utils;
 
-#[cfg(test)]
-mod tests;
+/// Ctrl-C 时等待卸载完成的超时时间
+const INTERRUPT_TIMEOUT: Duration = Duration::from_secs(30);
+/// 关闭控制台、注销、关机时等待卸载完成的超时时间(系统约5秒后强制结束进程)
+const CLOSE_TIMEOUT: Duration = Duration::from_secs(4);
 
-// 配置内置资源
-// x64平台
-#[cfg(target_arch = "x86_64")]
-#[derive(RustEmbed)]
-#[folder = "./assets-x64"]
-pub struct Asset;
-
-// x86平台
-#[cfg(target_arch = "x86")]
-#[derive(RustEmbed)]
-#[folder = "./assets-x86"]
-pub struct Asset;
-
-// ARM平台
-#[cfg(target_arch = "arm")]
-#[derive(RustEmbed)]
-#[folder = "./assets-ARM64"]
-pub struct Asset;
-
-// 设置静态变量
-lazy_static! {
-    pub static ref TEMP_PATH: PathBuf = env::temp_dir().join("ArchiveMount");
-}
+/// 是否正在退出
+static SHUTTING_DOWN: AtomicBool = AtomicBool::new(false);
 
 fn main() {
-    // 程序退出调用
-    let running = Arc::new(AtomicUsize::new(0));
-    ctrlc::set_handler(move || {
-        let prev = running.clone().fetch_add(1, Ordering::SeqCst);
-        if prev == 0 {
-            fs::remove_dir_all(&*TEMP_PATH).ok();
-            process::exit(0x0100);
-        }
-    }).expect("Error setting Ctrl-C handler");
+    // 程序退出调用(Ctrl-C、Ctrl-Break、关闭控制台、注销、关机)
+    unsafe { SetConsoleCtrlHandler(Some(consoleCtrlHandler), TRUE) };
 
     // 创建临时目录
     let _ = fs::create_dir(&*TEMP_PATH);
@@ -63,6 +35,33 @@ fn main() {
     // 处理CLI
     cli::cli();
 
-    // 删除临时目录
-    let _ = fs::remove_dir_all(&*TEMP_PATH);
+    removeTempPath();
+    process::exit(getExitCode());
+}
+
+/// 控制台事件处理(在系统创建的线程中调用，返回前完成卸载)
+unsafe extern "system" fn consoleCtrlHandler(ctrlType: DWORD) -> BOOL {
+    // 再次按下 Ctrl-C 时立即退出
+    if SHUTTING_DOWN.swap(true, Ordering::SeqCst) {
+        process::exit(0x0100);
+    }
+    writeConsole(ConsoleType::Info, "Shutting down, unmounting archives......");
+    let timeout = if ctrlType == CTRL_C_EVENT || ctrlType == CTRL_BREAK_EVENT { INTERRUPT_TIMEOUT } else { CLOSE_TIMEOUT };
+    // 卸载当前进程中的挂载，等待进行中的操作完成(卸载钩子提交修改、清理缓存)
+    if !unmountActiveMounts(timeout) {
+        writeConsole(ConsoleType::Warning, "Timed out waiting for the archives to unmount");
+    }
+    // 超时未结束的挂载随进程退出，删除其挂载记录
+    for record in registry::listMountRecords().iter().filter(|record| record.pid == process::id()) {
+        record.unregister();
+    }
+    removeTempPath();
+    process::exit(0x0100);
+}
+
+/// 删除临时目录(仍有其他挂载或管理进程运行时保留)
+fn removeTempPath() {
+    if registry::listMountRecords().is_empty() && !manager::isManagerRunning() {
+        let _ = fs::remove_dir_all(&*TEMP_PATH);
+    }
 }
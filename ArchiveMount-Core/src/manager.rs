@@ -0,0 +1,306 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::Ordering;
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+use anyhow::{anyhow, bail, Result};
+use interprocess::local_socket::{LocalSocketListener, LocalSocketStream, NameTypeSupport};
+use serde::{Deserialize, Serialize};
+
+use crate::ArchiveFS::ArchiveFS;
+use crate::mountBuilder::{MOUNT_TIMEOUT, startMount};
+use crate::mountOptions::{MountOptions, prepareMount};
+use crate::registry::{listMountRecords, MountRecord};
+use crate::TEMP_PATH;
+use crate::utils::console::{ConsoleType, writeConsole};
+use crate::utils::util::{getDirectorySize, getUnixTimestamp};
+
+/// 管理进程请求
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "command")]
+pub enum ManagerRequest {
+    /// 挂载压缩包
+    mount { options: MountOptions },
+    /// 卸载压缩包
+    unmount { mountPath: PathBuf },
+    /// 列出所有挂载
+    status,
+    /// 获取挂载的运行统计
+    stats { mountPath: PathBuf },
+    /// 停止管理进程(卸载所有挂载)
+    stop,
+}
+
+/// 管理进程响应
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ManagerResponse {
+    /// 是否成功
+    pub success: bool,
+    /// 提示信息
+    pub message: String,
+    /// 挂载状态
+    pub mounts: Vec<MountStatus>,
+}
+
+impl ManagerResponse {
+    fn ok(message: &str) -> ManagerResponse {
+        ManagerResponse { success: true, message: message.to_string(), mounts: Vec::new() }
+    }
+
+    fn err(message: &str) -> ManagerResponse {
+        ManagerResponse { success: false, message: message.to_string(), mounts: Vec::new() }
+    }
+}
+
+/// 挂载状态
+/// 打开的句柄数、读写字节数仅由同一进程中的挂载(管理进程或库调用)提供
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MountStatus {
+    /// 挂载记录
+    pub record: MountRecord,
+    /// 是否由管理进程托管
+    pub managed: bool,
+    /// 已挂载时长(单位: 秒)
+    pub uptime: u64,
+    /// 缓存占用大小(单位: 字节)
+    pub cacheUsage: u64,
+    /// 打开的句柄数
+    pub openHandles: Option<usize>,
+    /// 最后活动时间(UNIX时间戳，单位: 秒)
+    pub lastActivity: Option<u64>,
+    /// 读取字节数
+    pub readBytes: Option<u64>,
+    /// 写入字节数
+    pub writeBytes: Option<u64>,
+}
+
+impl MountStatus {
+    /// 由挂载记录生成挂载状态(统计缓存目录大小)
+    pub fn fromRecord(record: MountRecord) -> MountStatus {
+        MountStatus {
+            uptime: getUptime(&record),
+            cacheUsage: getDirectorySize(&record.extractPath),
+            record,
+            managed: false,
+            openHandles: None,
+            lastActivity: None,
+            readBytes: None,
+            writeBytes: None,
+        }
+    }
+
+    /// 由文件系统的运行统计生成挂载状态
+    pub fn fromArchiveFS(record: &MountRecord, archiveFS: &ArchiveFS) -> MountStatus {
+        let stats = archiveFS.stats();
+        MountStatus {
+            record: record.clone(),
+            managed: false,
+            uptime: getUptime(record),
+            cacheUsage: archiveFS.cacheUsage(),
+            openHandles: Some(stats.openHandles.load(Ordering::SeqCst)),
+            lastActivity: Some(stats.lastActivity.load(Ordering::Relaxed)),
+            readBytes: Some(stats.readBytes.load(Ordering::Relaxed)),
+            writeBytes: Some(stats.writeBytes.load(Ordering::Relaxed)),
+        }
+    }
+}
+
+/// 管理进程托管的挂载
+struct ManagedMount {
+    archiveFS: Arc<ArchiveFS>,
+    record: MountRecord,
+}
+
+impl ManagedMount {
+    fn status(&self) -> MountStatus {
+        let mut status = MountStatus::fromArchiveFS(&self.record, &self.archiveFS);
+        status.managed = true;
+        status
+    }
+}
+
+/// 挂载管理进程: 在同一进程中托管多个挂载，通过本地套接字接收请求
+/// 协议: 每个连接发送一行 JSON 请求，返回一行 JSON 响应
+pub struct Manager {
+    /// 挂载列表(小写挂载路径 -> 挂载)
+    mounts: Mutex<HashMap<String, ManagedMount>>,
+    /// 是否停止
+    stopped: Mutex<bool>,
+}
+
+impl Manager {
+    /// 启动管理进程并阻塞当前线程，直到收到停止请求
+    pub fn run() -> Result<()> {
+        if isManagerRunning() {
+            bail!("The manager is already running");
+        }
+        let socketName = getSocketName();
+        if !socketName.starts_with('@') {
+            fs::remove_file(&socketName).ok();
+        }
+        let listener = LocalSocketListener::bind(socketName.as_str())?;
+        let manager = Arc::new(Manager { mounts: Mutex::new(HashMap::new()), stopped: Mutex::new(false) });
+        writeConsole(ConsoleType::Success, &*format!("Manager started: {}", socketName));
+
+        for stream in listener.incoming() {
+            let stream = match stream {
+                Ok(stream) => stream,
+                Err(_) => continue,
+            };
+            if *manager.stopped.lock().unwrap() {
+                break;
+            }
+            let manager = manager.clone();
+            thread::spawn(move || manager.handleConnection(stream));
+        }
+
+        // 等待所有挂载线程完成清理
+        let startTime = SystemTime::now();
+        while !manager.mounts.lock().unwrap().is_empty() && startTime.elapsed().unwrap_or_default() < MOUNT_TIMEOUT {
+            thread::sleep(Duration::from_millis(100));
+        }
+        if !socketName.starts_with('@') {
+            fs::remove_file(&socketName).ok();
+        }
+        writeConsole(ConsoleType::Info, "Manager stopped");
+        Ok(())
+    }
+
+    /// 处理连接
+    fn handleConnection(self: Arc<Self>, stream: LocalSocketStream) {
+        let mut reader = BufReader::new(stream);
+        let mut line = String::new();
+        if reader.read_line(&mut line).is_err() {
+            return;
+        }
+        let response = match serde_json::from_str::<ManagerRequest>(&line) {
+            Ok(request) => self.clone().handleRequest(request),
+            Err(err) => ManagerResponse::err(&format!("Invalid request: {}", err)),
+        };
+        let mut stream = reader.into_inner();
+        if let Ok(content) = serde_json::to_string(&response) {
+            stream.write_all(format!("{}\n", content).as_bytes()).ok();
+        }
+        // 收到停止请求后连接一次使监听循环退出
+        if *self.stopped.lock().unwrap() {
+            LocalSocketStream::connect(getSocketName().as_str()).ok();
+        }
+    }
+
+    /// 处理请求
+    fn handleRequest(self: Arc<Self>, request: ManagerRequest) -> ManagerResponse {
+        match request {
+            ManagerRequest::mount { options } => match self.mount(options) {
+                Ok(mountPath) => ManagerResponse::ok(&format!("Mounted archive successfully: {}", mountPath.display())),
+                Err(err) => ManagerResponse::err(&err.to_string()),
+            },
+            ManagerRequest::unmount { mountPath } => {
+                if !self.mounts.lock().unwrap().contains_key(&getMountKey(&mountPath)) {
+                    return ManagerResponse::err("No archive is mounted at the mount path");
+                }
+                if ArchiveFS::unmount(&mountPath) {
+                    ManagerResponse::ok("unmount successfully")
+                } else {
+                    ManagerResponse::err("unmount failed")
+                }
+            }
+            ManagerRequest::status => {
+                let mut response = ManagerResponse::ok("");
+                response.mounts = self.mounts.lock().unwrap().values().map(|mount| mount.status()).collect();
+                response.mounts.sort_by(|a, b| a.record.mountPath.cmp(&b.record.mountPath));
+                response
+            }
+            ManagerRequest::stats { mountPath } => match self.mounts.lock().unwrap().get(&getMountKey(&mountPath)) {
+                Some(mount) => {
+                    let mut response = ManagerResponse::ok("");
+                    response.mounts.push(mount.status());
+                    response
+                }
+                None => ManagerResponse::err("No archive is mounted at the mount path"),
+            },
+            ManagerRequest::stop => {
+                *self.stopped.lock().unwrap() = true;
+                let mountPathList: Vec<PathBuf> = self.mounts.lock().unwrap().values().map(|mount| mount.record.mountPath.clone()).collect();
+                for mountPath in mountPathList {
+                    ArchiveFS::unmount(&mountPath);
+                }
+                ManagerResponse::ok("Manager stopped")
+            }
+        }
+    }
+
+    /// 在新线程中挂载，等待挂载完成或失败
+    fn mount(self: Arc<Self>, options: MountOptions) -> Result<PathBuf> {
+        if self.mounts.lock().unwrap().contains_key(&getMountKey(&options.mountPath)) {
+            bail!("An archive is already mounted at the mount path");
+        }
+        let prepared = prepareMount(&options, None)?;
+        let mountPath = prepared.mountPath.clone();
+        let key = getMountKey(&mountPath);
+        let record = prepared.mountRecord;
+        let lock = record.register().map_err(|err| anyhow!("Register mount record failed: {}", err))?;
+        let archiveFS = Arc::new(prepared.archiveFS);
+        self.mounts.lock().unwrap().insert(key.clone(), ManagedMount { archiveFS: archiveFS.clone(), record: record.clone() });
+
+        // 挂载结束后删除挂载记录
+        let manager = self.clone();
+        startMount(archiveFS, &mountPath, options.threadCount, prepared.prefetchList, prepared.idleTimeout, move || {
+            record.unregister();
+            drop(lock);
+            manager.mounts.lock().unwrap().remove(&key);
+        })?;
+        Ok(mountPath)
+    }
+}
+
+/// 本地套接字名称(Windows 为命名管道，其他平台为 Unix 套接字文件)
+pub fn getSocketName() -> String {
+    if NameTypeSupport::query().paths_supported() {
+        TEMP_PATH.join("manager.sock").to_str().unwrap().to_string()
+    } else {
+        "@ArchiveMount-Manager".to_string()
+    }
+}
+
+/// 管理进程是否正在运行
+pub fn isManagerRunning() -> bool {
+    LocalSocketStream::connect(getSocketName().as_str()).is_ok()
+}
+
+/// 向管理进程发送请求
+pub fn sendRequest(request: &ManagerRequest) -> Result<ManagerResponse> {
+    let mut stream = LocalSocketStream::connect(getSocketName().as_str()).map_err(|_| anyhow!("The manager is not running"))?;
+    stream.write_all(format!("{}\n", serde_json::to_string(request)?).as_bytes())?;
+    let mut line = String::new();
+    BufReader::new(stream).read_line(&mut line)?;
+    Ok(serde_json::from_str(&line)?)
+}
+
+/// 列出所有挂载(挂载记录，管理进程运行时使用其提供的运行统计)
+pub fn listMountStatus() -> Vec<MountStatus> {
+    let managedList = match sendRequest(&ManagerRequest::status) {
+        Ok(response) if response.success => response.mounts,
+        _ => Vec::new(),
+    };
+    let mut statusList: Vec<MountStatus> = listMountRecords().into_iter()
+        .filter(|record| !managedList.iter().any(|status| getMountKey(&status.record.mountPath) == getMountKey(&record.mountPath)))
+        .map(MountStatus::fromRecord)
+        .collect();
+    statusList.extend(managedList);
+    statusList.sort_by(|a, b| a.record.mountPath.cmp(&b.record.mountPath));
+    statusList
+}
+
+/// 已挂载时长
+fn getUptime(record: &MountRecord) -> u64 {
+    if record.mountedAt == 0 { 0 } else { getUnixTimestamp().saturating_sub(record.mountedAt) }
+}
+
+/// 挂载路径作为键(Z: 与 Z:\ 视为同一路径)
+fn getMountKey(mountPath: &Path) -> String {
+    mountPath.to_str().unwrap().trim_end_matches(|c| c == '\\' || c == '/').to_lowercase()
+}
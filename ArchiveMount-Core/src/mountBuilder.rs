@@ -0,0 +1,335 @@
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, mpsc, Mutex};
+use std::sync::atomic::Ordering;
+use std::thread;
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant, SystemTime};
+
+use anyhow::{anyhow, bail, Result};
+
+use crate::ArchiveFS::{ArchiveChangeMode, ArchiveFS};
+use crate::config::MountProfile;
+use crate::manager::MountStatus;
+use crate::mountOptions::{MountFailed, MountOptions, prepareMount};
+use crate::registry::MountRecord;
+use crate::utils::console::{ConsoleEvent, EventHandler};
+use crate::writeRule::PathPattern;
+
+/// 等待挂载完成的超时时间
+pub(crate) const MOUNT_TIMEOUT: Duration = Duration::from_secs(30);
+
+lazy_static! {
+    /// 当前进程中的挂载(挂载路径, 文件系统)，挂载线程结束后移除
+    static ref ACTIVE_MOUNTS: Mutex<Vec<(PathBuf, Arc<ArchiveFS>)>> = Mutex::new(Vec::new());
+}
+
+/// 挂载构建器
+///
+/// ```no_run
+/// use ArchiveMount::MountBuilder;
+///
+/// let handle = MountBuilder::new(r"D:\Archive.7z", "Z:")
+///     .password("123456")
+///     .cacheSize(1024)
+///     .onEvent(|event| println!("{:?}", event))
+///     .mount()?;
+/// println!("{:?}", handle.stats());
+/// handle.unmount()?;
+/// # Ok::<(), anyhow::Error>(())
+/// ```
+#[derive(Debug, Clone)]
+pub struct MountBuilder {
+    options: MountOptions,
+    eventHandler: Option<EventHandler>,
+}
+
+impl MountBuilder {
+    /// 参数1: 压缩包路径
+    /// 参数2: 挂载路径(盘符或空目录)
+    pub fn new(archivePath: impl AsRef<Path>, mountPath: impl AsRef<Path>) -> MountBuilder {
+        MountBuilder::fromOptions(MountOptions::new(archivePath.as_ref(), mountPath.as_ref()))
+    }
+
+    /// 由挂载参数创建
+    pub fn fromOptions(options: MountOptions) -> MountBuilder {
+        MountBuilder { options, eventHandler: None }
+    }
+
+    /// 压缩包密码
+    pub fn password(mut self, password: &str) -> MountBuilder {
+        self.options.password = Some(password.to_string());
+        self
+    }
+
+    /// 缓存目录(默认临时目录)
+    pub fn tempPath(mut self, tempPath: impl AsRef<Path>) -> MountBuilder {
+        self.options.tempPath = Some(tempPath.as_ref().to_path_buf());
+        self
+    }
+
+    /// 缓存大小(单位: MB)
+    pub fn cacheSize(mut self, cacheSize: u64) -> MountBuilder {
+        self.options.cacheSize = cacheSize;
+        self
+    }
+
+    /// 线程数(0为自动)
+    pub fn threadCount(mut self, threadCount: u16) -> MountBuilder {
+        self.options.threadCount = threadCount;
+        self
+    }
+
+    /// 只读挂载
+    pub fn readOnly(mut self, readOnly: bool) -> MountBuilder {
+        self.options.readOnly = readOnly;
+        self
+    }
+
+    /// 挂载卷标名
+    pub fn volumeName(mut self, volumeName: &str) -> MountBuilder {
+        self.options.volumeName = volumeName.to_string();
+        self
+    }
+
+    /// 叠加补丁压缩包(可多次调用，后添加的优先)
+    pub fn patch(mut self, patchPath: impl AsRef<Path>) -> MountBuilder {
+        self.options.patch.push(patchPath.as_ref().to_path_buf());
+        self
+    }
+
+    /// 持久覆盖层目录(写入的文件卸载后保留)
+    pub fn overlay(mut self, overlayPath: impl AsRef<Path>) -> MountBuilder {
+        self.options.overlay = Some(overlayPath.as_ref().to_path_buf());
+        self
+    }
+
+    /// 写入规则文件及配置目录(persist 规则)
+    pub fn rules(mut self, rulesPath: impl AsRef<Path>, profilePath: Option<&Path>) -> MountBuilder {
+        self.options.rules = Some(rulesPath.as_ref().to_path_buf());
+        self.options.profileDir = profilePath.map(|profilePath| profilePath.to_path_buf());
+        self
+    }
+
+    /// 空闲(无打开的句柄且无读写)超过时长后自动卸载
+    pub fn idleTimeout(mut self, idleTimeout: Duration) -> MountBuilder {
+        self.options.idleTimeout = Some(idleTimeout);
+        self
+    }
+
+    /// 卸载时将覆盖层的修改提交至压缩包
+    pub fn commitOnUnmount(mut self, commitOnUnmount: bool) -> MountBuilder {
+        self.options.commitOnUnmount = commitOnUnmount;
+        self
+    }
+
+    /// 挂载压缩包目录时子目录名去除扩展名
+    pub fn stripExtension(mut self, stripExtension: bool) -> MountBuilder {
+        self.options.stripExtension = stripExtension;
+        self
+    }
+
+    /// 将压缩包中的压缩包显示为目录(展开的最大层数，0为不展开)
+    pub fn nestedDepth(mut self, nestedDepth: u32) -> MountBuilder {
+        self.options.nestedDepth = nestedDepth;
+        self
+    }
+
+    /// 将压缩包中的目录作为挂载的根目录
+    pub fn root(mut self, root: &str) -> MountBuilder {
+        self.options.root = Some(root.to_string());
+        self
+    }
+
+    /// 去除压缩包的顶层目录(每层仅包含一个目录)
+    pub fn stripComponents(mut self, stripComponents: u32) -> MountBuilder {
+        self.options.stripComponents = stripComponents;
+        self
+    }
+
+    /// 仅显示匹配的条目(glob 模式，可多次调用)
+    pub fn include(mut self, pattern: &str) -> MountBuilder {
+        self.options.include.push(pattern.to_string());
+        self
+    }
+
+    /// 隐藏匹配的条目(glob 模式，可多次调用)
+    pub fn exclude(mut self, pattern: &str) -> MountBuilder {
+        self.options.exclude.push(pattern.to_string());
+        self
+    }
+
+    /// 压缩包在磁盘上变化时的处理方式(默认重新读取)
+    pub fn onArchiveChange(mut self, archiveChangeMode: ArchiveChangeMode) -> MountBuilder {
+        self.options.onArchiveChange = archiveChangeMode;
+        self
+    }
+
+    /// 挂载后预先解压匹配的文件(glob 模式，可多次调用)
+    pub fn prefetch(mut self, pattern: &str) -> MountBuilder {
+        self.options.prefetch.push(pattern.to_string());
+        self
+    }
+
+    /// 应用挂载配置中的配置项(已设置的参数被覆盖)
+    pub fn profile(mut self, profile: &MountProfile) -> Result<MountBuilder> {
+        self.options.applyProfile(profile, |_| false)?;
+        Ok(self)
+    }
+
+    /// 读取压缩包的 7-Zip 程序(默认内置 7z.exe)
+    pub fn sevenZip(mut self, zipProgram: impl AsRef<Path>) -> MountBuilder {
+        self.options.sevenZip = Some(zipProgram.as_ref().to_path_buf());
+        self
+    }
+
+    /// 事件回调(设置后挂载的事件不再输出至控制台)
+    pub fn onEvent(mut self, handler: impl Fn(&ConsoleEvent) + Send + Sync + 'static) -> MountBuilder {
+        self.eventHandler = Some(EventHandler::new(handler));
+        self
+    }
+
+    /// 挂载参数
+    pub(crate) fn options(&self) -> &MountOptions {
+        &self.options
+    }
+
+    /// 事件回调
+    pub(crate) fn eventHandler(&self) -> Option<&EventHandler> {
+        self.eventHandler.as_ref()
+    }
+
+    /// 挂载，挂载完成或失败后返回
+    pub fn mount(self) -> Result<MountHandle> {
+        let prepared = prepareMount(&self.options, self.eventHandler.as_ref())?;
+        let record = prepared.mountRecord;
+        let lock = record.register().map_err(|err| anyhow!("Register mount record failed: {}", err))?;
+        let archiveFS = Arc::new(prepared.archiveFS);
+        let finishedRecord = record.clone();
+        let thread = startMount(archiveFS.clone(), &prepared.mountPath, self.options.threadCount, prepared.prefetchList, prepared.idleTimeout, move || {
+            finishedRecord.unregister();
+            drop(lock);
+        })?;
+        Ok(MountHandle { archiveFS, record, thread: Some(thread) })
+    }
+}
+
+/// 挂载句柄(释放时卸载)
+#[derive(Debug)]
+pub struct MountHandle {
+    archiveFS: Arc<ArchiveFS>,
+    record: MountRecord,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl MountHandle {
+    /// 挂载路径
+    pub fn mountPath(&self) -> &Path {
+        &self.record.mountPath
+    }
+
+    /// 挂载记录
+    pub fn record(&self) -> &MountRecord {
+        &self.record
+    }
+
+    /// 是否仍处于挂载状态
+    pub fn isMounted(&self) -> bool {
+        self.archiveFS.stats().mounted.load(Ordering::SeqCst)
+    }
+
+    /// 运行统计
+    pub fn stats(&self) -> MountStatus {
+        MountStatus::fromArchiveFS(&self.record, &self.archiveFS)
+    }
+
+    /// 卸载并等待挂载线程结束
+    pub fn unmount(mut self) -> Result<()> {
+        self.unmountInner()
+    }
+
+    /// 阻塞至卸载(由其他进程卸载或弹出)
+    pub fn wait(mut self) -> Result<()> {
+        if let Some(thread) = self.thread.take() {
+            thread.join().ok();
+        }
+        Ok(())
+    }
+
+    fn unmountInner(&mut self) -> Result<()> {
+        if let Some(thread) = self.thread.take() {
+            if self.isMounted() && !ArchiveFS::unmount(&self.record.mountPath) {
+                self.thread = Some(thread);
+                bail!("unmount failed");
+            }
+            thread.join().ok();
+        }
+        Ok(())
+    }
+}
+
+impl Drop for MountHandle {
+    fn drop(&mut self) {
+        self.unmountInner().ok();
+    }
+}
+
+/// 在新线程中挂载，等待挂载完成或失败
+/// 参数4: 挂载完成后预先解压的文件
+/// 参数5: 空闲自动卸载的时长
+/// 参数6: 挂载结束(卸载或挂载失败)后调用
+pub(crate) fn startMount(archiveFS: Arc<ArchiveFS>, mountPath: &Path, threadCount: u16, prefetchList: Vec<PathPattern>, idleTimeout: Option<Duration>, finished: impl FnOnce() + Send + 'static) -> Result<JoinHandle<()>> {
+    // 挂载线程(阻塞至卸载)
+    let (sender, receiver) = mpsc::channel();
+    let mountArchiveFS = archiveFS.clone();
+    let threadMountPath: PathBuf = mountPath.to_path_buf();
+    ACTIVE_MOUNTS.lock().unwrap().push((threadMountPath.clone(), archiveFS.clone()));
+    let thread = thread::spawn(move || {
+        let result = mountArchiveFS.mount(&threadMountPath, threadCount);
+        ACTIVE_MOUNTS.lock().unwrap().retain(|(_, item)| !Arc::ptr_eq(item, &mountArchiveFS));
+        finished();
+        sender.send(result).ok();
+    });
+
+    let startTime = SystemTime::now();
+    loop {
+        if archiveFS.stats().mounted.load(Ordering::SeqCst) {
+            let watchArchiveFS = archiveFS.clone();
+            thread::spawn(move || watchArchiveFS.watchArchive());
+            if let Some(idleTimeout) = idleTimeout {
+                let idleArchiveFS = archiveFS.clone();
+                let idleMountPath = mountPath.to_path_buf();
+                thread::spawn(move || idleArchiveFS.watchIdle(&idleMountPath, idleTimeout));
+            }
+            if !prefetchList.is_empty() {
+                thread::spawn(move || archiveFS.prefetch(&prefetchList));
+            }
+            return Ok(thread);
+        }
+        match receiver.recv_timeout(Duration::from_millis(100)) {
+            Ok(Err(err)) => return Err(MountFailed(err).into()),
+            Ok(Ok(())) => bail!("The archive was unmounted"),
+            Err(_) => {}
+        }
+        if startTime.elapsed().unwrap_or_default() > MOUNT_TIMEOUT {
+            ArchiveFS::unmount(mountPath);
+            bail!("Mount timed out");
+        }
+    }
+}
+
+/// 卸载当前进程中的所有挂载，等待挂载线程结束(进行中的操作完成、缓存清理)
+/// 返回是否在超时前全部结束
+pub fn unmountActiveMounts(timeout: Duration) -> bool {
+    let mountPathList: Vec<PathBuf> = ACTIVE_MOUNTS.lock().unwrap().iter().map(|(mountPath, _)| mountPath.clone()).collect();
+    for mountPath in mountPathList.iter() {
+        ArchiveFS::unmount(mountPath);
+    }
+    let startTime = Instant::now();
+    while !ACTIVE_MOUNTS.lock().unwrap().is_empty() {
+        if startTime.elapsed() > timeout {
+            return false;
+        }
+        thread::sleep(Duration::from_millis(100));
+    }
+    true
+}
@@ -0,0 +1,542 @@
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::time::Duration;
+
+use anyhow::{anyhow, bail, Result};
+use clap::Args;
+use dokan::MountError;
+use serde::{Deserialize, Serialize};
+
+use crate::ArchiveFS::{ArchiveChangeMode, ArchiveFS};
+use crate::archiveLayer::{ArchiveDirectory, ArchiveLayer, listNestedLayers};
+use crate::config::{ManifestEntry, MountConfig, MountProfile};
+use crate::entryFilter::EntryFilter;
+use crate::overlay::Overlay;
+use crate::registry::{MountLock, MountRecord};
+use crate::sevenZip::sevenZip;
+use crate::TEMP_PATH;
+use crate::utils::console::{ConsoleEvent, ConsoleType, emitEvent, EventHandler};
+use crate::utils::util::{createVirtualDrive, getAbsolutePath, getPathHash, getUnixTimestamp, parseDuration};
+use crate::volume::resolveVolumes;
+use crate::writeRule::{PathPattern, WritePolicy, WriteRules};
+
+/// 挂载参数(命令行参数，同时作为管理进程的挂载请求)
+#[derive(Args, Debug, Clone, Serialize, Deserialize)]
+pub struct MountOptions {
+    /// Archive path(more archives can be given before the mount path, later archives take precedence), or a directory of archives
+    pub archivePath: PathBuf,
+    /// Mount path
+    pub mountPath: PathBuf,
+    /// Temporary path(default temporary directory)
+    pub tempPath: Option<PathBuf>,
+    /// Archives stacked on top of the archive(given before the mount path)
+    #[clap(hide = true)]
+    #[serde(default)]
+    pub stackedPaths: Vec<PathBuf>,
+    /// Archive password
+    #[clap(short, long)]
+    pub password: Option<String>,
+    /// Threads(0: auto)
+    #[clap(short, long)]
+    #[clap(default_value_t = 0_u16)]
+    pub threadCount: u16,
+    /// Cache size(unit: MB)
+    #[clap(short, long)]
+    #[clap(default_value_t = 4096)]
+    pub cacheSize: u64,
+    /// read only mount
+    #[clap(short, long)]
+    pub readOnly: bool,
+    /// nest mount points
+    #[clap(short, long)]
+    pub nest: bool,
+    /// After mount open explorer
+    #[clap(short, long)]
+    pub open: bool,
+    /// Mount volume name
+    #[clap(short, long)]
+    #[clap(default_value_t = String::from("ArchiveMount"))]
+    pub volumeName: String,
+    /// Debug mode
+    #[clap(short, long)]
+    pub debug: bool,
+    /// Patch archive stacked on top of the archive(can be repeated, later patches take precedence)
+    #[clap(long)]
+    pub patch: Vec<PathBuf>,
+    /// Persistent overlay directory(written files are kept after unmount)
+    #[clap(long)]
+    pub overlay: Option<PathBuf>,
+    /// Write rule file(each line: persist|discard|deny <glob pattern>)
+    #[clap(long)]
+    pub rules: Option<PathBuf>,
+    /// Profile directory for files matching persist rules
+    #[clap(long)]
+    pub profileDir: Option<PathBuf>,
+    /// 7-Zip program used to read archives(default built-in 7z.exe)
+    #[clap(long)]
+    #[serde(default)]
+    pub sevenZip: Option<PathBuf>,
+    /// Unmount after the volume has been idle for the duration(e.g. 30s, 10m, 1h)
+    #[clap(long, parse(try_from_str = parseDuration))]
+    #[serde(default)]
+    pub idleTimeout: Option<Duration>,
+    /// Files extracted into the cache after mount(glob pattern, can be repeated)
+    #[clap(long)]
+    #[serde(default)]
+    pub prefetch: Vec<String>,
+    /// Only show archive entries matching the pattern(glob pattern, can be repeated)
+    #[clap(long)]
+    #[serde(default)]
+    pub include: Vec<String>,
+    /// Hide archive entries matching the pattern(glob pattern, can be repeated)
+    #[clap(long)]
+    #[serde(default)]
+    pub exclude: Vec<String>,
+    /// When the archive changes on disk: reload the file list, refuse reads, or ignore
+    #[clap(long, arg_enum, default_value = "reload")]
+    #[serde(default = "defaultArchiveChangeMode")]
+    pub onArchiveChange: ArchiveChangeMode,
+    /// Commit the overlay changes to the archive on unmount
+    #[clap(long)]
+    #[serde(default)]
+    pub commitOnUnmount: bool,
+    /// Strip the archive extension from subdirectory names when mounting a directory of archives
+    #[clap(long)]
+    #[serde(default)]
+    pub stripExtension: bool,
+    /// Show archives inside the archive as directories, up to the nesting depth(0: off)
+    #[clap(long)]
+    #[clap(default_value_t = 0_u32)]
+    #[serde(default)]
+    pub nestedDepth: u32,
+    /// Directory in the archive to mount as the root
+    #[clap(long)]
+    #[serde(default)]
+    pub root: Option<String>,
+    /// Strip the top-level directories of the archive, each must be the only entry of its level
+    #[clap(long)]
+    #[clap(default_value_t = 0_u32)]
+    #[serde(default)]
+    pub stripComponents: u32,
+    /// Mount config file(default ArchiveMount.toml next to the program, then %APPDATA%\ArchiveMount\config.toml)
+    #[clap(long)]
+    #[serde(skip)]
+    pub config: Option<PathBuf>,
+    /// Profile in the mount config
+    #[clap(long)]
+    #[serde(skip)]
+    pub profile: Option<String>,
+}
+
+impl MountOptions {
+    /// 默认挂载参数(与命令行默认值一致)
+    pub fn new(archivePath: &Path, mountPath: &Path) -> MountOptions {
+        MountOptions {
+            archivePath: archivePath.to_path_buf(),
+            mountPath: mountPath.to_path_buf(),
+            tempPath: None,
+            stackedPaths: Vec::new(),
+            password: None,
+            threadCount: 0,
+            cacheSize: 4096,
+            readOnly: false,
+            nest: false,
+            open: false,
+            volumeName: String::from("ArchiveMount"),
+            debug: false,
+            patch: Vec::new(),
+            overlay: None,
+            rules: None,
+            profileDir: None,
+            sevenZip: None,
+            idleTimeout: None,
+            prefetch: Vec::new(),
+            include: Vec::new(),
+            exclude: Vec::new(),
+            onArchiveChange: ArchiveChangeMode::reload,
+            commitOnUnmount: false,
+            stripExtension: false,
+            nestedDepth: 0,
+            root: None,
+            stripComponents: 0,
+            config: None,
+            profile: None,
+        }
+    }
+
+    /// 应用挂载配置(命令行中指定的参数不被覆盖)
+    /// 参数2: 参数是否在命令行中指定(参数名为字段名)
+    pub fn applyProfile(&mut self, profile: &MountProfile, isExplicit: impl Fn(&str) -> bool) -> Result<()> {
+        macro_rules! applyFields {
+            ($($field:ident),*) => {
+                $(if let (Some(value), false) = (&profile.$field, isExplicit(stringify!($field))) { self.$field = value.clone().into(); })*
+            };
+        }
+        applyFields!(tempPath, cacheSize, threadCount, readOnly, nest, open, volumeName, patch, overlay, rules, profileDir, sevenZip, prefetch, include, exclude, onArchiveChange, commitOnUnmount, stripExtension, nestedDepth, root, stripComponents);
+        if let (Some(idleTimeout), false) = (&profile.idleTimeout, isExplicit("idleTimeout")) {
+            self.idleTimeout = Some(parseDuration(idleTimeout)?);
+        }
+        if !isExplicit("password") {
+            if let Some(password) = profile.getPassword()? {
+                self.password = Some(password);
+            }
+        }
+        Ok(())
+    }
+
+    /// 整理命令行中的位置参数: 压缩包、叠加的压缩包、挂载路径、缓存目录
+    /// 如 mount base.7z dlc1.7z dlc2.7z Z:，开头已存在的文件均为压缩包(后面的优先)
+    pub fn resolveStackedPaths(&mut self) -> Result<()> {
+        if self.stackedPaths.is_empty() && !self.mountPath.is_file() {
+            return Ok(());
+        }
+        let mut pathList = vec![self.archivePath.clone(), self.mountPath.clone()];
+        pathList.extend(self.tempPath.take());
+        pathList.append(&mut self.stackedPaths);
+        // 至少保留一个挂载路径
+        let archiveCount = pathList.iter().take_while(|path| path.is_file()).count().clamp(1, pathList.len() - 1);
+        if pathList.len() > archiveCount + 2 {
+            bail!("Too many paths, the mount path must follow the archives");
+        }
+        let mut pathList = pathList.into_iter();
+        self.archivePath = pathList.next().unwrap();
+        self.stackedPaths = (&mut pathList).take(archiveCount - 1).collect();
+        self.mountPath = pathList.next().unwrap();
+        self.tempPath = pathList.next();
+        Ok(())
+    }
+
+    /// 读取挂载配置并应用(未指定配置文件时使用默认配置文件，不存在时忽略)
+    pub fn applyConfig(&mut self, isExplicit: impl Fn(&str) -> bool) -> Result<()> {
+        let configPath = match (&self.config, MountConfig::findDefaultPath()) {
+            (Some(config), _) => getAbsolutePath(config),
+            (None, Some(defaultPath)) => defaultPath,
+            _ if self.profile.is_some() => bail!("The mount config file does not exist, please specify it with --config"),
+            _ => return Ok(()),
+        };
+        let config = MountConfig::load(&configPath).map_err(|err| anyhow!("Read mount config failed: {}", err))?;
+        let profile = config.resolve(&getAbsolutePath(&self.archivePath), self.profile.as_deref())?;
+        self.applyProfile(&profile, isExplicit)
+    }
+
+    /// 由挂载清单项创建(清单项中的配置优先于挂载配置)
+    /// 参数2: 挂载配置文件(未指定时使用默认配置文件)
+    pub fn fromManifestEntry(entry: &ManifestEntry, config: Option<&Path>) -> Result<MountOptions> {
+        let mut options = MountOptions::new(&entry.archivePath, &entry.mountPath);
+        options.config = config.map(|config| config.to_path_buf());
+        options.profile = entry.profile.clone();
+        options.applyConfig(|_| false)?;
+        options.applyProfile(&entry.options, |_| false)?;
+        Ok(options)
+    }
+
+    /// 将相对路径转换为绝对路径(转发至管理进程前调用，管理进程的工作目录可能不同)
+    pub fn absolutize(&mut self) {
+        self.archivePath = getAbsolutePath(&self.archivePath);
+        self.tempPath = self.tempPath.as_deref().map(getAbsolutePath);
+        self.stackedPaths = self.stackedPaths.iter().map(|stackedPath| getAbsolutePath(stackedPath)).collect();
+        self.patch = self.patch.iter().map(|patchPath| getAbsolutePath(patchPath)).collect();
+        self.overlay = self.overlay.as_deref().map(getAbsolutePath);
+        self.rules = self.rules.as_deref().map(getAbsolutePath);
+        self.profileDir = self.profileDir.as_deref().map(getAbsolutePath);
+        self.sevenZip = self.sevenZip.as_deref().map(getAbsolutePath);
+    }
+}
+
+fn defaultArchiveChangeMode() -> ArchiveChangeMode {
+    ArchiveChangeMode::reload
+}
+
+/// 准备完成的挂载
+pub struct PreparedMount {
+    /// 文件系统
+    pub archiveFS: ArchiveFS,
+    /// 挂载记录
+    pub mountRecord: MountRecord,
+    /// 实际挂载路径(嵌套挂载时为子目录)
+    pub mountPath: PathBuf,
+    /// 挂载后预先解压的文件
+    pub prefetchList: Vec<PathPattern>,
+    /// 空闲自动卸载的时长
+    pub idleTimeout: Option<Duration>,
+}
+
+/// 准备挂载: 检查参数、读取压缩包列表、创建覆盖层
+/// 参数2: 事件回调(未设置时输出至控制台)
+pub fn prepareMount(options: &MountOptions, eventHandler: Option<&EventHandler>) -> Result<PreparedMount> {
+    let emit = |consoleType: ConsoleType, message: &str| emitEvent(eventHandler, ConsoleEvent::new(consoleType, message));
+    emit(ConsoleType::Progress, &*format!("Mounting archive: {}", options.archivePath.to_str().unwrap()));
+    if dokan::driver_version() == 0 {
+        bail!("driver not installed, Please make sure you install the Dokan driver");
+    }
+    // 处理相对目录
+    let archivePath = getAbsolutePath(&options.archivePath);
+    if !archivePath.exists() {
+        bail!("The archive does not exist, if the path contains spaces please use quotation marks.");
+    }
+    // 挂载压缩包目录(每个压缩包为一个子目录)
+    let isDirectory = archivePath.is_dir();
+    if isDirectory && (!options.stackedPaths.is_empty() || !options.patch.is_empty()) {
+        bail!("Stacked or patch archives are not supported when mounting a directory of archives");
+    }
+    if isDirectory && options.commitOnUnmount {
+        bail!("Commit on unmount is not supported when mounting a directory of archives");
+    }
+    if options.nestedDepth > 0 && options.commitOnUnmount {
+        bail!("Commit on unmount is not supported when nested archives are expanded");
+    }
+    // 分卷压缩包(可指定任意分卷)使用打开压缩包的分卷，名称去除分卷编号
+    let (archivePath, archiveName, isMultiVolume) = if isDirectory {
+        let archiveName = archivePath.file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or_default();
+        (archivePath, archiveName, false)
+    } else {
+        let volumeSet = resolveVolumes(&archivePath)?;
+        if volumeSet.volumeList.len() > 1 && options.commitOnUnmount {
+            bail!("Commit on unmount is not supported for multi-volume archives");
+        }
+        let isMultiVolume = volumeSet.volumeList.len() > 1;
+        (volumeSet.primaryPath, volumeSet.name, isMultiVolume)
+    };
+    // 叠加的压缩包在补丁压缩包之下
+    let mut patchPathList = Vec::new();
+    for stackedPath in options.stackedPaths.iter() {
+        let stackedPath = getAbsolutePath(stackedPath);
+        if !stackedPath.exists() {
+            bail!("The archive does not exist: {}", stackedPath.display());
+        }
+        patchPathList.push(resolveVolumes(&stackedPath)?.primaryPath);
+    }
+    for patchPath in options.patch.iter() {
+        let patchPath = getAbsolutePath(patchPath);
+        if !patchPath.exists() {
+            bail!("The patch archive does not exist: {}", patchPath.display());
+        }
+        patchPathList.push(resolveVolumes(&patchPath)?.primaryPath);
+    }
+
+    // 处理挂载路径
+    let mut mountPath = options.mountPath.clone();
+    if mountPath.is_dir() {
+        if mountPath.metadata().unwrap().len() != 0 {
+            // 挂载路径为目录则需 1.目录存在 2.不能在挂载前打开 3.目录为空目录
+            bail!("The mount path is not empty, please specify an empty directory");
+        }
+        // 尝试创建目录
+        fs::create_dir_all(&mountPath).ok();
+    }
+
+    let mut virtualDrive = None;
+    if options.nest {
+        mountPath = if mountPath.is_dir() {
+            mountPath.join(&archiveName)
+        } else {
+            let mountParent = TEMP_PATH.join("MountPoint");
+            let mountPint = mountParent.join(&archiveName);
+            if fs::create_dir_all(&mountPint).is_err() || !createVirtualDrive(&*mountParent, &*mountPath) {
+                bail!("Create virtual mount point failed, please try to unnested mount point");
+            };
+            virtualDrive = Some(mountPath.clone());
+            mountPint
+            // mountPath.join(archivePath.file_name().unwrap())
+        };
+    }
+
+    // 处理缓存目录(每个压缩包层使用独立的缓存目录)
+    // 目录名包含压缩包实际路径的哈希值(区分不同目录下的同名压缩包)，由锁文件保证同一时间仅被一个挂载使用
+    let tempPath = options.tempPath.as_deref().map(getAbsolutePath);
+    let extractParentPath = if let Some(tempPath) = &tempPath { tempPath.clone() } else { TEMP_PATH.join("ArchiveTemp") };
+    let canonicalPath = fs::canonicalize(&archivePath).unwrap_or_else(|_| archivePath.clone());
+    let baseName = format!("{}-{}", archiveName, &getPathHash(&canonicalPath)[..8]);
+    let (cacheName, cacheLock) = lockCacheName(&extractParentPath, &baseName)?;
+    let extractPath = extractParentPath.join(&cacheName);
+    // 清理异常退出的挂载遗留的缓存(锁已释放)
+    fs::remove_dir_all(&extractPath).ok();
+    // 处理覆盖层目录(写入的文件，指定持久覆盖层时卸载后保留)
+    let overlayPath = match &options.overlay {
+        Some(overlay) => getAbsolutePath(overlay),
+        None => {
+            let overlayPath = if let Some(tempPath) = &tempPath { tempPath.clone() } else { TEMP_PATH.clone() }.join("ArchiveOverlay").join(&cacheName);
+            fs::remove_dir_all(&overlayPath).ok();
+            overlayPath
+        }
+    };
+    let overlay = if options.overlay.is_some() { Overlay::newPersistent(&overlayPath, &archivePath) } else { Overlay::new(&overlayPath) };
+    let overlay = match overlay {
+        Ok(overlay) => overlay,
+        Err(err) => bail!("Open overlay directory failed: {}", err),
+    };
+    let password = options.password.as_deref();
+
+    // 读取写入规则
+    let writeRules = match &options.rules {
+        Some(rules) => match WriteRules::load(&getAbsolutePath(rules)) {
+            Ok(writeRules) => writeRules,
+            Err(err) => bail!("Read write rules failed: {}", err),
+        },
+        None => WriteRules::default(),
+    };
+    if writeRules.hasPolicy(WritePolicy::Persist) && options.profileDir.is_none() {
+        bail!("The write rules contain persist rules, please specify the profile directory");
+    }
+    // 配置目录覆盖层(persist 规则)
+    let profileOverlay = match &options.profileDir {
+        Some(profile) => match Overlay::newPersistent(&getAbsolutePath(profile), &archivePath) {
+            Ok(overlay) => Some(overlay),
+            Err(err) => bail!("Open profile directory failed: {}", err),
+        },
+        None => None,
+    };
+    // 丢弃覆盖层(discard 规则，每次挂载重新创建)
+    let discardOverlay = if writeRules.hasPolicy(WritePolicy::Discard) {
+        let discardPath = if let Some(tempPath) = &tempPath { tempPath.clone() } else { TEMP_PATH.clone() }.join("ArchiveOverlay").join(format!("{}-discard", cacheName));
+        fs::remove_dir_all(&discardPath).ok();
+        Overlay::new(&discardPath).ok()
+    } else {
+        None
+    };
+
+    let mut prefetchList = Vec::new();
+    for pattern in options.prefetch.iter() {
+        match PathPattern::new(pattern) {
+            Ok(pattern) => prefetchList.push(pattern),
+            Err(err) => bail!("Invalid prefetch pattern: {}", err),
+        }
+    }
+
+    // 读取压缩包信息
+    emit(ConsoleType::Progress, "Reading archive list......");
+    let sevenZip = match &options.sevenZip {
+        Some(zipProgram) => sevenZip::withProgram(&getAbsolutePath(zipProgram))?,
+        None => sevenZip::new()?,
+    };
+    // 压缩包目录中的压缩包在首次访问时读取文件列表
+    let archiveDirectory = if isDirectory {
+        Some(ArchiveDirectory { path: archivePath.clone(), password: options.password.clone(), extractPath: extractPath.clone(), stripExtension: options.stripExtension })
+    } else {
+        None
+    };
+    let mut layers = match &archiveDirectory {
+        Some(archiveDirectory) => archiveDirectory.listNewLayers(&[]),
+        None => {
+            let archiveLayer = ArchiveLayer::new(&sevenZip, &*archivePath, password, &extractPath)?;
+            if archiveLayer.archiveFileInfoList.is_empty() {
+                bail!("The Archive information is not detected, please confirm it is the correct archive or encrypted archive");
+            }
+            vec![archiveLayer]
+        }
+    };
+    for (index, patchPath) in patchPathList.iter().enumerate() {
+        let layerName = if index < options.stackedPaths.len() { "archive" } else { "patch archive" };
+        emit(ConsoleType::Progress, &*format!("Reading {}: {}", layerName, patchPath.to_str().unwrap()));
+        let patchExtractPath = extractParentPath.join(format!("{}-patch{}", cacheName, index + 1));
+        fs::remove_dir_all(&patchExtractPath).ok();
+        match ArchiveLayer::new(&sevenZip, patchPath, password, &patchExtractPath) {
+            Ok(layer) => layers.push(layer),
+            Err(err) => bail!("Read {} failed: {}", layerName, err),
+        }
+    }
+    // 嵌套压缩包在首次访问时解压并读取文件列表
+    for index in 0..layers.len() {
+        let nestedLayers = listNestedLayers(&layers, index, options.nestedDepth);
+        layers.extend(nestedLayers);
+    }
+
+    // 挂载记录
+    let mut cachePathList: Vec<PathBuf> = layers.iter().skip(1).filter(|layer| layer.source.is_none()).map(|layer| layer.extractPath.clone()).collect();
+    cachePathList.extend(discardOverlay.iter().map(|overlay| overlay.rootPath().to_path_buf()));
+    let mountRecord = MountRecord {
+        pid: process::id(),
+        archivePath: archivePath.clone(),
+        mountPath: mountPath.clone(),
+        extractPath,
+        overlayPath,
+        readOnly: options.readOnly,
+        persistentOverlay: options.overlay.is_some(),
+        commitSupported: !isDirectory && options.nestedDepth == 0 && !isMultiVolume,
+        mountedAt: getUnixTimestamp(),
+        virtualDrive,
+        cachePathList,
+    };
+
+    let mut archiveFS = ArchiveFS::new(sevenZip, layers, overlay, options.cacheSize, options.readOnly, options.open, &options.volumeName, options.debug);
+    archiveFS.setWriteRules(writeRules, profileOverlay, discardOverlay);
+    archiveFS.setEventHandler(eventHandler.cloned());
+    archiveFS.setArchiveChangeMode(options.onArchiveChange);
+    archiveFS.setCommitOnUnmount(options.commitOnUnmount);
+    archiveFS.setCacheLock(cacheLock);
+    archiveFS.setNestedDepth(options.nestedDepth);
+    // 根目录按完整的文件列表查找，过滤规则匹配去除根目录后的路径
+    archiveFS.setRootPath(options.root.as_deref(), options.stripComponents)?;
+    archiveFS.setEntryFilter(EntryFilter::new(&options.include, &options.exclude)?);
+    if let Some(archiveDirectory) = archiveDirectory {
+        archiveFS.setArchiveDirectory(archiveDirectory);
+    }
+    Ok(PreparedMount { archiveFS, mountRecord, mountPath, prefetchList, idleTimeout: options.idleTimeout })
+}
+
+/// 获取未被其他挂载使用的缓存目录名(同一压缩包同时挂载多次时依次编号)及其锁
+fn lockCacheName(extractParentPath: &Path, baseName: &str) -> Result<(String, MountLock)> {
+    fs::create_dir_all(extractParentPath)?;
+    for index in 1..=100 {
+        let cacheName = if index == 1 { baseName.to_string() } else { format!("{}-{}", baseName, index) };
+        if let Ok(lock) = MountLock::acquire(&extractParentPath.join(format!("{}.lock", cacheName))) {
+            return Ok((cacheName, lock));
+        }
+    }
+    bail!("Lock cache directory failed: {}", extractParentPath.display())
+}
+
+/// 挂载失败(Dokan 挂载错误)
+#[derive(Debug)]
+pub struct MountFailed(pub MountError);
+
+impl MountFailed {
+    /// 退出码
+    pub fn code(&self) -> i32 {
+        getMountErrorCode(&self.0)
+    }
+}
+
+impl fmt::Display for MountFailed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(getMountErrorMessage(&self.0))
+    }
+}
+
+impl std::error::Error for MountFailed {}
+
+/// 挂载错误的退出码
+pub fn getMountErrorCode(err: &MountError) -> i32 {
+    match err {
+        MountError::Error => 10,
+        MountError::DriveLetterError => 11,
+        MountError::DriverInstallError => 12,
+        MountError::StartError => 13,
+        MountError::MountError => 14,
+        MountError::MountPointError => 15,
+        MountError::VersionError => 16,
+    }
+}
+
+/// 挂载错误退出码对应的错误信息
+pub fn getMountErrorCodeMessage(code: i32) -> Option<&'static str> {
+    [MountError::Error, MountError::DriveLetterError, MountError::DriverInstallError, MountError::StartError, MountError::MountError, MountError::MountPointError, MountError::VersionError]
+        .iter()
+        .find(|err| getMountErrorCode(err) == code)
+        .map(getMountErrorMessage)
+}
+
+/// 挂载错误信息
+pub fn getMountErrorMessage(err: &MountError) -> &'static str {
+    match err {
+        MountError::Error => "An error occurred",
+        MountError::DriveLetterError => "Drive letter error",
+        MountError::DriverInstallError => "Can't install the Dokan driver",
+        MountError::StartError => "The driver responds that something is wrong",
+        MountError::MountError => "Can't assign a drive letter or mount point",
+        MountError::MountPointError => "The mount point is invalid",
+        MountError::VersionError => "The Dokan version that this wrapper is targeting is incompatible with the loaded Dokan library",
+    }
+}
@@ -0,0 +1,344 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+use anyhow::{bail, Result};
+
+use crate::archiveLayer::DELETIONS_FILE_NAME;
+use crate::sevenZip::{sevenZip, UPDATABLE_ARCHIVE_TYPES};
+use crate::utils::util::{copyDirectory, replaceFile};
+
+/// 删除记录文件名(每行一个已删除的压缩包条目)
+const WHITEOUT_FILE_NAME: &str = "whiteout.txt";
+/// 持久覆盖层所属压缩包记录文件名
+const ARCHIVE_FILE_NAME: &str = "archive.txt";
+/// 快照目录名
+const SNAPSHOTS_DIR_NAME: &str = "snapshots";
+
+/// 可写覆盖层
+/// 目录结构:
+/// - upper: 程序新增或修改的文件
+/// - whiteout.txt: 已删除的压缩包条目
+/// - archive.txt: 所属压缩包路径(仅持久覆盖层)
+/// - snapshots: 快照(每个快照为一个目录，保存 upper 与 whiteout.txt 的副本)
+#[derive(Debug)]
+pub struct Overlay {
+    /// 覆盖层根目录
+    rootPath: PathBuf,
+    /// 上层文件目录
+    upperPath: PathBuf,
+    /// 已删除条目(小写路径 -> 原始路径)
+    whiteoutList: Mutex<HashMap<String, String>>,
+    /// 删除记录文件的修改时间(用于检测其他进程的修改，如挂载中回滚快照)
+    whiteoutModified: Mutex<Option<SystemTime>>,
+    /// 是否为持久覆盖层(卸载后保留)
+    persistent: bool,
+}
+
+impl Overlay {
+    pub fn new(rootPath: &Path) -> Result<Overlay> {
+        let upperPath = rootPath.join("upper");
+        fs::create_dir_all(&upperPath)?;
+        let whiteoutFilePath = rootPath.join(WHITEOUT_FILE_NAME);
+        Ok(Overlay {
+            rootPath: rootPath.to_path_buf(),
+            upperPath,
+            whiteoutList: Mutex::new(readWhiteoutList(&whiteoutFilePath)),
+            whiteoutModified: Mutex::new(getModifiedTime(&whiteoutFilePath)),
+            persistent: false,
+        })
+    }
+
+    /// 打开用户指定的持久覆盖层，覆盖层已属于其他压缩包时返回错误
+    pub fn newPersistent(rootPath: &Path, archivePath: &Path) -> Result<Overlay> {
+        let archiveFilePath = rootPath.join(ARCHIVE_FILE_NAME);
+        if let Ok(content) = fs::read_to_string(&archiveFilePath) {
+            if !content.trim().is_empty() && !content.trim().eq_ignore_ascii_case(archivePath.to_str().unwrap()) {
+                bail!("The overlay directory belongs to another archive: {}", content.trim());
+            }
+        }
+        let mut overlay = Overlay::new(rootPath)?;
+        fs::write(&archiveFilePath, archivePath.to_str().unwrap())?;
+        overlay.persistent = true;
+        Ok(overlay)
+    }
+
+    /// 是否为持久覆盖层
+    pub fn isPersistent(&self) -> bool {
+        self.persistent
+    }
+
+    /// 覆盖层根目录
+    pub fn rootPath(&self) -> &Path {
+        &self.rootPath
+    }
+
+    /// 上层文件目录
+    pub fn upperPath(&self) -> &Path {
+        &self.upperPath
+    }
+
+    /// 删除记录文件被其他进程修改时重新读取
+    pub fn refresh(&self) {
+        let whiteoutFilePath = self.rootPath.join(WHITEOUT_FILE_NAME);
+        let modified = getModifiedTime(&whiteoutFilePath);
+        let mut whiteoutList = self.whiteoutList.lock().unwrap();
+        let mut whiteoutModified = self.whiteoutModified.lock().unwrap();
+        if *whiteoutModified != modified {
+            *whiteoutList = readWhiteoutList(&whiteoutFilePath);
+            *whiteoutModified = modified;
+        }
+    }
+
+    /// 获取文件在上层中的路径
+    pub fn getUpperFilePath(&self, relativePath: &str) -> PathBuf {
+        self.upperPath.join(normalizePath(relativePath))
+    }
+
+    /// 判断条目是否已被删除(自身或任意上级目录被删除)
+    pub fn isDeleted(&self, relativePath: &str) -> bool {
+        let whiteoutList = self.whiteoutList.lock().unwrap();
+        if whiteoutList.is_empty() {
+            return false;
+        }
+        let path = normalizePath(relativePath).to_lowercase();
+        let mut current = path.as_str();
+        loop {
+            if whiteoutList.contains_key(current) {
+                return true;
+            }
+            match current.rfind('\\') {
+                Some(index) => current = &current[..index],
+                None => return false,
+            }
+        }
+    }
+
+    /// 记录删除条目
+    pub fn addWhiteout(&self, relativePath: &str) {
+        let path = normalizePath(relativePath);
+        let mut whiteoutList = self.whiteoutList.lock().unwrap();
+        whiteoutList.insert(path.to_lowercase(), path);
+        self.save(&whiteoutList);
+    }
+
+    /// 移除删除记录(仅移除自身)
+    pub fn removeWhiteout(&self, relativePath: &str) -> bool {
+        let path = normalizePath(relativePath).to_lowercase();
+        let mut whiteoutList = self.whiteoutList.lock().unwrap();
+        let removed = whiteoutList.remove(&path).is_some();
+        if removed {
+            self.save(&whiteoutList);
+        }
+        removed
+    }
+
+    /// 已删除条目列表
+    pub fn whiteoutList(&self) -> Vec<String> {
+        let mut list: Vec<String> = self.whiteoutList.lock().unwrap().values().cloned().collect();
+        list.sort();
+        list
+    }
+
+    /// 是否存在未提交的修改
+    pub fn hasChanges(&self) -> bool {
+        let hasUpperFiles = self.upperPath.read_dir().map(|mut entries| entries.next().is_some()).unwrap_or(false);
+        hasUpperFiles || !self.whiteoutList.lock().unwrap().is_empty()
+    }
+
+    /// 将覆盖层的修改(新增、修改、删除、重命名)提交至压缩包
+    /// 修改先应用到新压缩包，成功后再替换原压缩包
+    pub fn commit(&self, sevenZip: &sevenZip, archivePath: &Path, password: Option<&str>) -> Result<()> {
+        if !self.hasChanges() {
+            return Ok(());
+        }
+        let archiveType = sevenZip.getArchiveType(archivePath, password)?;
+        if !UPDATABLE_ARCHIVE_TYPES.contains(&&*archiveType.to_lowercase()) {
+            bail!("The archive format '{}' does not support updates", archiveType);
+        }
+        let headerEncrypted = password.is_some() && sevenZip.isHeaderEncrypted(archivePath)?;
+
+        // 在原压缩包所在目录生成新压缩包
+        let fileName = archivePath.file_name().unwrap().to_str().unwrap();
+        let newArchivePath = archivePath.with_file_name(format!("~commit-{}", fileName));
+        fs::copy(archivePath, &newArchivePath)?;
+        let result = (|| -> Result<()> {
+            let whiteoutList = self.whiteoutList();
+            if !whiteoutList.is_empty() && !sevenZip.deleteArchiveFiles(&newArchivePath, password, &whiteoutList, headerEncrypted)? {
+                bail!("Failed to delete files from the archive");
+            }
+            if self.upperPath.read_dir()?.next().is_some() && !sevenZip.updateArchiveFile(&newArchivePath, &self.upperPath, password, headerEncrypted)? {
+                bail!("Failed to update files in the archive");
+            }
+            Ok(())
+        })();
+        if let Err(err) = result {
+            fs::remove_file(&newArchivePath).ok();
+            return Err(err);
+        }
+
+        // 替换原压缩包，成功后清空覆盖层(修改已写入压缩包，避免再次提交或遮盖压缩包中的条目)
+        replaceFile(&newArchivePath, archivePath)?;
+        self.clear();
+        Ok(())
+    }
+
+    /// 清空上层文件及删除记录
+    fn clear(&self) {
+        if let Ok(entries) = self.upperPath.read_dir() {
+            for entry in entries.flatten() {
+                if entry.path().is_dir() {
+                    fs::remove_dir_all(entry.path()).ok();
+                } else {
+                    fs::remove_file(entry.path()).ok();
+                }
+            }
+        }
+        let mut whiteoutList = self.whiteoutList.lock().unwrap();
+        whiteoutList.clear();
+        self.save(&whiteoutList);
+    }
+
+    /// 将覆盖层的修改导出为补丁压缩包(新增或修改的文件，以及删除清单)
+    pub fn exportChanges(&self, sevenZip: &sevenZip, patchPath: &Path, password: Option<&str>) -> Result<()> {
+        if patchPath.exists() {
+            bail!("The patch archive already exists: {}", patchPath.display());
+        }
+        let mut fileList = Vec::new();
+        if self.upperPath.read_dir()?.next().is_some() {
+            fileList.push(format!("{}\\*", self.upperPath.to_str().unwrap()));
+        }
+
+        // 删除清单写入临时目录后添加到补丁压缩包根目录
+        let exportPath = self.rootPath.join("export");
+        let whiteoutList = self.whiteoutList();
+        if !whiteoutList.is_empty() {
+            fs::create_dir_all(&exportPath)?;
+            let deletionsFilePath = exportPath.join(DELETIONS_FILE_NAME);
+            fs::write(&deletionsFilePath, whiteoutList.join("\r\n"))?;
+            fileList.push(deletionsFilePath.to_str().unwrap().to_string());
+        }
+        if fileList.is_empty() {
+            bail!("There are no changes to export");
+        }
+
+        let result = sevenZip.addArchiveFiles(patchPath, &fileList, password);
+        fs::remove_dir_all(&exportPath).ok();
+        if !result? {
+            fs::remove_file(patchPath).ok();
+            bail!("Failed to create the patch archive");
+        }
+        Ok(())
+    }
+
+    /// 创建快照(保存当前的上层文件及删除记录)
+    pub fn createSnapshot(&self, name: &str) -> Result<()> {
+        checkSnapshotName(name)?;
+        let snapshotPath = self.rootPath.join(SNAPSHOTS_DIR_NAME).join(name);
+        if snapshotPath.exists() {
+            bail!("The snapshot already exists: {}", name);
+        }
+        let result = (|| -> Result<()> {
+            copyDirectory(&self.upperPath, &snapshotPath.join("upper"))?;
+            let whiteoutFilePath = self.rootPath.join(WHITEOUT_FILE_NAME);
+            if whiteoutFilePath.exists() {
+                fs::copy(&whiteoutFilePath, snapshotPath.join(WHITEOUT_FILE_NAME))?;
+            }
+            Ok(())
+        })();
+        if result.is_err() {
+            fs::remove_dir_all(&snapshotPath).ok();
+        }
+        result
+    }
+
+    /// 快照列表(快照名, 创建时间)，按创建时间排序
+    pub fn listSnapshots(&self) -> Vec<(String, SystemTime)> {
+        let mut snapshotList = Vec::new();
+        if let Ok(entries) = self.rootPath.join(SNAPSHOTS_DIR_NAME).read_dir() {
+            for entry in entries.flatten() {
+                if entry.path().is_dir() {
+                    let created = entry.metadata().and_then(|metadata| metadata.created()).unwrap_or(SystemTime::UNIX_EPOCH);
+                    snapshotList.push((entry.file_name().to_string_lossy().to_string(), created));
+                }
+            }
+        }
+        snapshotList.sort_by(|a, b| a.1.cmp(&b.1));
+        snapshotList
+    }
+
+    /// 回滚至快照，未指定快照时回滚至初始状态(清空所有修改)
+    pub fn rollback(&self, name: Option<&str>) -> Result<()> {
+        let snapshotPath = match name {
+            Some(name) => {
+                checkSnapshotName(name)?;
+                let snapshotPath = self.rootPath.join(SNAPSHOTS_DIR_NAME).join(name);
+                if !snapshotPath.is_dir() {
+                    bail!("The snapshot does not exist: {}", name);
+                }
+                Some(snapshotPath)
+            }
+            None => None,
+        };
+
+        // 清空上层文件
+        if self.upperPath.exists() {
+            fs::remove_dir_all(&self.upperPath)?;
+        }
+        fs::create_dir_all(&self.upperPath)?;
+
+        // 还原快照(删除记录重新写入，使挂载中的进程检测到修改)
+        let whiteoutFilePath = self.rootPath.join(WHITEOUT_FILE_NAME);
+        fs::remove_file(&whiteoutFilePath).ok();
+        if let Some(snapshotPath) = snapshotPath {
+            copyDirectory(&snapshotPath.join("upper"), &self.upperPath)?;
+            if let Ok(content) = fs::read(snapshotPath.join(WHITEOUT_FILE_NAME)) {
+                fs::write(&whiteoutFilePath, content)?;
+            }
+        }
+        self.refresh();
+        Ok(())
+    }
+
+    /// 保存删除记录
+    fn save(&self, whiteoutList: &HashMap<String, String>) {
+        let mut list: Vec<&String> = whiteoutList.values().collect();
+        list.sort();
+        let content = list.iter().map(|path| path.as_str()).collect::<Vec<&str>>().join("\r\n");
+        let whiteoutFilePath = self.rootPath.join(WHITEOUT_FILE_NAME);
+        fs::write(&whiteoutFilePath, content).ok();
+        *self.whiteoutModified.lock().unwrap() = getModifiedTime(&whiteoutFilePath);
+    }
+}
+
+/// 读取删除记录
+fn readWhiteoutList(whiteoutFilePath: &Path) -> HashMap<String, String> {
+    match fs::read_to_string(whiteoutFilePath) {
+        Ok(content) => content.lines()
+            .map(normalizePath)
+            .filter(|path| !path.is_empty())
+            .map(|path| (path.to_lowercase(), path))
+            .collect(),
+        Err(_) => HashMap::new(),
+    }
+}
+
+/// 获取文件修改时间(文件不存在时为 None)
+fn getModifiedTime(filePath: &Path) -> Option<SystemTime> {
+    filePath.metadata().and_then(|metadata| metadata.modified()).ok()
+}
+
+/// 检查快照名(不能为空或包含路径分隔符)
+fn checkSnapshotName(name: &str) -> Result<()> {
+    if name.trim().is_empty() || name.contains(|c| c == '\\' || c == '/' || c == ':') || name == "." || name == ".." {
+        bail!("Invalid snapshot name: {}", name);
+    }
+    Ok(())
+}
+
+/// 统一路径格式(去除首尾分隔符，使用 \ 分隔)
+pub fn normalizePath(path: &str) -> String {
+    path.replace('/', "\\").trim_matches('\\').to_string()
+}
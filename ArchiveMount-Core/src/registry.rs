@@ -0,0 +1,227 @@
+use std::fs;
+use std::fs::{File, OpenOptions};
+use std::os::windows::fs::OpenOptionsExt;
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use crate::TEMP_PATH;
+use crate::utils::util::{getPathHash, isProcessRunning, removeVirtualDrive};
+
+/// 共享模式: 允许读取，不允许删除(持有锁的进程退出后锁文件才能被删除)
+const FILE_SHARE_READ: u32 = 1;
+
+/// 挂载记录
+/// 每个挂载对应 Registry 目录下的一个文件，供其他进程(提交、卸载)查找挂载信息
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MountRecord {
+    /// 挂载进程ID
+    pub pid: u32,
+    /// 压缩包路径
+    pub archivePath: PathBuf,
+    /// 挂载路径
+    pub mountPath: PathBuf,
+    /// 缓存目录
+    pub extractPath: PathBuf,
+    /// 覆盖层目录
+    pub overlayPath: PathBuf,
+    /// 是否只读挂载
+    pub readOnly: bool,
+    /// 是否为持久覆盖层
+    #[serde(default)]
+    pub persistentOverlay: bool,
+    /// 是否支持提交修改(目录挂载、展开嵌套压缩包及分卷压缩包不支持)
+    #[serde(default)]
+    pub commitSupported: bool,
+    /// 挂载时间(UNIX时间戳，单位: 秒)
+    #[serde(default)]
+    pub mountedAt: u64,
+    /// 嵌套挂载创建的虚拟盘符
+    #[serde(default)]
+    pub virtualDrive: Option<PathBuf>,
+    /// 其他缓存目录(补丁压缩包缓存、丢弃覆盖层)
+    #[serde(default)]
+    pub cachePathList: Vec<PathBuf>,
+}
+
+/// 挂载锁(挂载进程持有，释放时删除锁文件)
+#[derive(Debug)]
+pub struct MountLock {
+    file: Option<File>,
+    path: PathBuf,
+}
+
+impl MountLock {
+    /// 创建并持有锁文件，锁文件被其他进程持有时失败
+    pub(crate) fn acquire(path: &Path) -> Result<MountLock> {
+        let file = OpenOptions::new().write(true).create(true).truncate(true).share_mode(FILE_SHARE_READ).open(path)?;
+        Ok(MountLock { file: Some(file), path: path.to_path_buf() })
+    }
+}
+
+impl Drop for MountLock {
+    fn drop(&mut self) {
+        self.file.take();
+        fs::remove_file(&self.path).ok();
+    }
+}
+
+impl MountRecord {
+    /// 写入挂载记录，返回挂载锁(挂载结束前持有)
+    pub fn register(&self) -> Result<MountLock> {
+        fs::create_dir_all(getRegistryPath())?;
+        let lock = MountLock::acquire(&getRecordPath(&self.mountPath).with_extension("lock"))?;
+        fs::write(getRecordPath(&self.mountPath), serde_json::to_string_pretty(self)?)?;
+        Ok(lock)
+    }
+
+    /// 删除挂载记录
+    pub fn unregister(&self) {
+        fs::remove_file(getRecordPath(&self.mountPath)).ok();
+    }
+
+    /// 请求挂载进程卸载时保留覆盖层(由提交修改的进程在卸载后提交并清理)
+    pub fn requestCommit(&self) -> Result<()> {
+        fs::write(getRecordPath(&self.mountPath).with_extension("commit"), "")?;
+        Ok(())
+    }
+
+    /// 取消保留覆盖层的请求
+    pub fn cancelCommit(&self) {
+        fs::remove_file(getRecordPath(&self.mountPath).with_extension("commit")).ok();
+    }
+
+    /// 等待挂载结束(挂载锁被释放)，超时返回 false
+    pub fn waitReleased(&self, timeout: Duration) -> bool {
+        let lockPath = getRecordPath(&self.mountPath).with_extension("lock");
+        let startTime = Instant::now();
+        while lockPath.exists() && fs::remove_file(&lockPath).is_err() {
+            if startTime.elapsed() >= timeout {
+                return false;
+            }
+            thread::sleep(Duration::from_millis(100));
+        }
+        true
+    }
+
+    /// 挂载进程是否已退出(如崩溃)，挂载遗留的缓存需要清理
+    /// 未找到锁文件时(旧版本的挂载记录)根据进程ID判断
+    pub fn isStale(&self) -> bool {
+        let lockPath = getRecordPath(&self.mountPath).with_extension("lock");
+        if lockPath.exists() {
+            // 锁文件在挂载进程退出前无法删除
+            return fs::remove_file(&lockPath).is_ok();
+        }
+        !isProcessRunning(self.pid)
+    }
+
+    /// 清理已退出的挂载进程遗留的虚拟盘符、挂载目录、缓存目录及挂载记录
+    pub fn cleanup(&self) {
+        if let Some(virtualDrive) = &self.virtualDrive {
+            removeVirtualDrive(virtualDrive);
+        }
+        // 仅删除程序创建的挂载目录(嵌套挂载、运行程序)
+        if self.mountPath.starts_with(TEMP_PATH.join("MountPoint")) {
+            fs::remove_dir_all(&self.mountPath).ok();
+        }
+        // 缓存目录已被新的挂载锁定时保留
+        let lockPath = self.extractPath.with_file_name(format!("{}.lock", self.extractPath.file_name().unwrap_or_default().to_string_lossy()));
+        if !lockPath.exists() || fs::remove_file(&lockPath).is_ok() {
+            fs::remove_dir_all(&self.extractPath).ok();
+            for cachePath in self.cachePathList.iter() {
+                fs::remove_dir_all(cachePath).ok();
+            }
+            if !self.persistentOverlay {
+                fs::remove_dir_all(&self.overlayPath).ok();
+            }
+        }
+        self.unregister();
+    }
+}
+
+/// 根据挂载路径查找挂载记录
+pub fn findMountRecord(mountPath: &Path) -> Option<MountRecord> {
+    let content = fs::read_to_string(getRecordPath(mountPath)).ok()?;
+    serde_json::from_str(&content).ok()
+}
+
+/// 挂载路径是否已请求保留覆盖层(卸载后提交修改)
+pub fn isCommitRequested(mountPath: &Path) -> bool {
+    getRecordPath(mountPath).with_extension("commit").exists()
+}
+
+/// 列出所有挂载记录
+pub fn listMountRecords() -> Vec<MountRecord> {
+    let mut recordList = Vec::new();
+    if let Ok(entries) = getRegistryPath().read_dir() {
+        for entry in entries.flatten().filter(|entry| entry.path().extension().map(|extension| extension == "json").unwrap_or(false)) {
+            if let Ok(content) = fs::read_to_string(entry.path()) {
+                if let Ok(record) = serde_json::from_str(&content) {
+                    recordList.push(record);
+                }
+            }
+        }
+    }
+    recordList
+}
+
+/// 清理已退出的挂载进程遗留的挂载，返回清理的挂载记录
+pub fn cleanupStaleMounts() -> Vec<MountRecord> {
+    let staleList: Vec<MountRecord> = listMountRecords().into_iter().filter(|record| record.isStale()).collect();
+    for record in staleList.iter() {
+        record.cleanup();
+    }
+    staleList
+}
+
+/// 删除临时目录中不属于任何挂载的缓存目录、覆盖层目录及挂载目录，返回删除的目录
+/// 需在清理遗留的挂载后调用
+pub fn removeOrphanedPaths() -> Vec<PathBuf> {
+    let recordList = listMountRecords();
+    // 被锁定的缓存目录名(准备挂载中，尚未写入挂载记录)，未被持有的锁文件直接删除
+    let mut lockedList = Vec::new();
+    if let Ok(entries) = TEMP_PATH.join("ArchiveTemp").read_dir() {
+        for entry in entries.flatten() {
+            let fileName = entry.file_name().to_string_lossy().to_string();
+            if let Some(cacheName) = fileName.strip_suffix(".lock") {
+                if fs::remove_file(entry.path()).is_err() {
+                    lockedList.push(cacheName.to_string());
+                }
+            }
+        }
+    }
+    let isUsed = |path: &Path| {
+        let fileName = path.file_name().unwrap().to_string_lossy();
+        lockedList.iter().any(|cacheName| fileName == cacheName.as_str() || fileName.starts_with(&format!("{}-", cacheName))) || recordList.iter().any(|record| {
+            record.mountPath == path || record.extractPath == path || record.overlayPath == path || record.cachePathList.iter().any(|cachePath| cachePath == path)
+        })
+    };
+    let mut removedList = Vec::new();
+    for parentPath in [TEMP_PATH.join("ArchiveTemp"), TEMP_PATH.join("ArchiveOverlay"), TEMP_PATH.join("MountPoint")] {
+        let entries = match parentPath.read_dir() {
+            Ok(entries) => entries,
+            Err(_) => continue,
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.is_dir() && !isUsed(&path) && fs::remove_dir_all(&path).is_ok() {
+                removedList.push(path);
+            }
+        }
+    }
+    removedList
+}
+
+/// 挂载记录目录
+fn getRegistryPath() -> PathBuf {
+    TEMP_PATH.join("Registry")
+}
+
+/// 挂载记录文件路径(由挂载路径的哈希值生成，Z: 与 Z:\ 视为同一路径)
+fn getRecordPath(mountPath: &Path) -> PathBuf {
+    let mountPath = mountPath.to_str().unwrap().trim_end_matches(|c| c == '\\' || c == '/');
+    getRegistryPath().join(format!("{}.json", getPathHash(Path::new(mountPath))))
+}
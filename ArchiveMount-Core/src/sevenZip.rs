@@ -3,7 +3,7 @@ use std::os::windows::process::CommandExt;
 use std::path::{Path, PathBuf};
 use std::process::Command;
 
-use anyhow::Result;
+use anyhow::{bail, Result};
 
 use crate::TEMP_PATH;
 use crate::utils::util::{String_utils, writeEmbedFile};
@@ -22,16 +22,43 @@ pub struct ArchiveFileInfo {
     pub(crate) Created: Option<String>,
     /// 是否为目录
     pub(crate) is_dir: bool,
+    /// 所属压缩包层(0为基础压缩包)
+    pub(crate) Layer: usize,
+    /// CRC校验码(部分格式无此属性)
+    pub(crate) CRC: String,
     // 文件属性
     // pub(crate) Attributes: String,
     // 是否加密
     // pub(crate) Encrypted: bool,
-    // CRC校验码
-    // pub(crate) CRC: String,
     // 压缩算法
     // pub(crate) Method: String,
 }
 
+impl ArchiveFileInfo {
+    /// 文件路径(压缩包内的相对路径)
+    pub fn path(&self) -> &str {
+        &self.Path
+    }
+
+    /// 文件大小
+    pub fn size(&self) -> u64 {
+        self.Size
+    }
+
+    /// 文件大小(压缩后)
+    pub fn packedSize(&self) -> u64 {
+        self.PackedSize
+    }
+
+    /// 是否为目录
+    pub fn isDir(&self) -> bool {
+        self.is_dir
+    }
+}
+
+/// 支持更新(写回)的压缩包格式
+pub const UPDATABLE_ARCHIVE_TYPES: [&str; 4] = ["7z", "zip", "tar", "wim"];
+
 #[derive(Debug)]
 pub struct sevenZip {
     zipProgram: PathBuf,
@@ -49,6 +76,14 @@ impl sevenZip {
         Ok(sevenZip { zipProgram })
     }
 
+    /// 使用指定的 7-Zip 程序(如已安装的 7z.exe)
+    pub fn withProgram(zipProgram: &Path) -> Result<sevenZip> {
+        if !zipProgram.is_file() {
+            bail!("The 7-Zip program does not exist: {}", zipProgram.display());
+        }
+        Ok(sevenZip { zipProgram: zipProgram.to_path_buf() })
+    }
+
     /// 列出压缩包文件列表
     pub fn listArchiveFiles(&self, zipFile: &Path, password: Option<&str>) -> Result<Vec<ArchiveFileInfo>> {
         let output = Command::new(&self.zipProgram).creation_flags(0x08000000)
@@ -81,9 +116,10 @@ impl sevenZip {
                 Modified: item.get_string_center("Modified = ", "\r\n").unwrap_or_else(|_| "".to_string()),
                 Created: created,
                 is_dir: item.get_string_center("Attributes = ", "\r\n").unwrap_or_else(|_| "".to_string()).contains('D'),
+                Layer: 0,
+                CRC: item.get_string_center("CRC = ", "\r\n").unwrap_or_else(|_| "".to_string()),
                 // Attributes: item.get_string_center("Attributes = ", "\r\n").unwrap_or_else(|_| "".to_string()),
                 // Encrypted: false,
-                // CRC: item.get_string_center("CRC = ", "\r\n").unwrap_or_else(|_| "".to_string()),
                 // Method: item.get_string_center("Method = ", "\r\n").unwrap_or_else(|_| "".to_string()),
             });
         }
@@ -120,28 +156,134 @@ impl sevenZip {
         Ok(outContent.contains("Everything is Ok"))
     }
 
+    /// 获取压缩包格式(如 7z、zip)
+    pub fn getArchiveType(&self, zipFile: &Path, password: Option<&str>) -> Result<String> {
+        let output = Command::new(&self.zipProgram).creation_flags(0x08000000)
+            .arg("l")
+            .arg(format!("-p{}", password.unwrap_or("")))
+            .arg("-slt")
+            .arg("-sccUTF-8")
+            .arg(zipFile.to_str().unwrap())
+            .output()?;
+        let content = String::from_utf8_lossy(&output.stdout);
+        Ok(content.get_string_center("Type = ", "\r\n").unwrap_or_else(|_| "".to_string()))
+    }
+
+    /// 压缩包信息(7-Zip 列出压缩包的全部输出，包括错误信息)
+    pub fn getArchiveInfo(&self, zipFile: &Path, password: Option<&str>) -> Result<String> {
+        let output = Command::new(&self.zipProgram).creation_flags(0x08000000)
+            .arg("l")
+            .arg(format!("-p{}", password.unwrap_or("")))
+            .arg("-slt")
+            .arg("-sccUTF-8")
+            .arg(zipFile.to_str().unwrap())
+            .output()?;
+        Ok(format!("{}\r\n{}", String::from_utf8_lossy(&output.stdout), String::from_utf8_lossy(&output.stderr)))
+    }
+
+    /// 压缩包是否加密了文件列表(未提供密码时无法列出文件)
+    pub fn isHeaderEncrypted(&self, zipFile: &Path) -> Result<bool> {
+        let output = Command::new(&self.zipProgram).creation_flags(0x08000000)
+            .arg("l")
+            .arg("-p")
+            .arg(zipFile.to_str().unwrap())
+            .output()?;
+        let content = String::from_utf8_lossy(&output.stderr);
+        Ok(!output.status.success() && content.contains("encrypted"))
+    }
+
     /// 7-zip 更新文件
     ///
     /// # 参数
     /// 1. 压缩包路径
-    /// 2. 更新路径
+    /// 2. 更新路径(目录内的文件以相对路径写入压缩包)
     /// 3. 压缩包密码
+    /// 4. 是否加密文件列表
     pub fn updateArchiveFile(
         &self,
         zipFile: &Path,
         updatePath: &Path,
         password: Option<&str>,
+        headerEncrypted: bool,
     ) -> Result<bool> {
-
-        // 7z.exe u 1.zip p0q3x2z0 D:\1\* -r
-        let output = Command::new(&self.zipProgram).creation_flags(0x08000000)
+        // 7z.exe u 1.zip -up1q1r2x2y2z2w2 D:\1\* -r
+        // 压缩包中存在而目录中不存在的文件保留，其余均以目录中的文件为准
+        let mut command = Command::new(&self.zipProgram);
+        command.creation_flags(0x08000000)
             .arg("u")
             .arg(zipFile)
-            .arg("p0q3x2z0")
+            .arg("-up1q1r2x2y2z2w2")
             .arg(format!("{}\\*", updatePath.to_str().unwrap()))
-            .arg("-r")
-            .arg(format!("-p{}", password.unwrap_or("")))
-            .output()?;
+            .arg("-r");
+        if let Some(password) = password {
+            command.arg(format!("-p{}", password));
+        }
+        if headerEncrypted {
+            command.arg("-mhe=on");
+        }
+        let output = command.output()?;
+        let outContent = String::from_utf8_lossy(&output.stdout);
+        Ok(outContent.contains("Everything is Ok"))
+    }
+
+    /// 7-zip 删除文件
+    ///
+    /// # 参数
+    /// 1. 压缩包路径
+    /// 2. 压缩包密码
+    /// 3. 删除的文件列表
+    /// 4. 是否加密文件列表
+    pub fn deleteArchiveFiles(
+        &self,
+        zipFile: &Path,
+        password: Option<&str>,
+        fileList: &[String],
+        headerEncrypted: bool,
+    ) -> Result<bool> {
+        // 通过列表文件传递，避免命令行过长
+        let listFilePath = TEMP_PATH.join(format!("delete-{}.txt", std::process::id()));
+        fs::write(&listFilePath, fileList.join("\r\n"))?;
+        let mut command = Command::new(&self.zipProgram);
+        command.creation_flags(0x08000000)
+            .arg("d")
+            .arg(zipFile)
+            .arg(format!("@{}", listFilePath.to_str().unwrap()))
+            .arg("-scsUTF-8");
+        if let Some(password) = password {
+            command.arg(format!("-p{}", password));
+        }
+        if headerEncrypted {
+            command.arg("-mhe=on");
+        }
+        let output = command.output();
+        fs::remove_file(&listFilePath).ok();
+        let output = output?;
+        let outContent = String::from_utf8_lossy(&output.stdout);
+        Ok(output.status.success() || outContent.contains("Everything is Ok"))
+    }
+
+    /// 7-zip 添加文件(压缩包不存在时创建，格式由扩展名决定)
+    ///
+    /// # 参数
+    /// 1. 压缩包路径
+    /// 2. 添加的文件列表(支持通配符)
+    /// 3. 压缩包密码
+    pub fn addArchiveFiles(
+        &self,
+        zipFile: &Path,
+        fileList: &[String],
+        password: Option<&str>,
+    ) -> Result<bool> {
+        let mut command = Command::new(&self.zipProgram);
+        command.creation_flags(0x08000000)
+            .arg("a")
+            .arg(zipFile)
+            .args(fileList)
+            .arg("-r");
+        if let Some(password) = password {
+            command.arg(format!("-p{}", password));
+        }
+        let output = command.output()?;
         let outContent = String::from_utf8_lossy(&output.stdout);
         Ok(outContent.contains("Everything is Ok"))
     }
@@ -4,13 +4,31 @@
 // 禁用未使用代码警告
 #![allow(dead_code)]
 
+use std::fs;
 use std::iter::zip;
 use std::path::{Path, PathBuf};
-use std::time::SystemTime;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
 
-use crate::ArchiveFS::ArchiveFS;
+use dokan::OperationError;
+use widestring::U16CString;
+use winapi::shared::ntstatus::{STATUS_DIRECTORY_NOT_EMPTY, STATUS_FILE_INVALID, STATUS_INVALID_PARAMETER};
+
+use crate::ArchiveFS::{ArchiveChangeMode, ArchiveFS, FILE_CREATE, FILE_OPEN, getChangedLayers};
+use crate::archiveLayer::{ArchiveDirectory, ArchiveLayer, listNestedLayers, mergeLayers};
+use crate::config::{MountConfig, MountManifest, MountProfile};
+use crate::entryFilter::EntryFilter;
+use crate::manager::{ManagerRequest, MountStatus};
+use crate::mountBuilder::{MountBuilder, unmountActiveMounts};
+use crate::mountOptions::MountOptions;
+use crate::overlay::Overlay;
+use crate::registry::{findMountRecord, isCommitRequested, MountRecord};
 use crate::sevenZip::{ArchiveFileInfo, sevenZip};
 use crate::TEMP_PATH;
+use crate::utils::console::{ConsoleEvent, ConsoleType, emitEvent};
+use crate::utils::util::{getPathHash, parseDuration};
+use crate::volume::{checkVolumes, parseVolumeFileName, resolveVolumes};
+use crate::writeRule::{WritePolicy, WriteRules};
 
 #[test]
 fn test_listArchiveFiles() {
@@ -25,9 +43,10 @@ fn test_listArchiveFiles() {
 fn test_mountArchive() {
     let zip = sevenZip::new().unwrap();
     let archivePath = PathBuf::from(r"./test/test.7z");
-    let archiveFileInfoList = zip.listArchiveFiles(&archivePath, None).unwrap();
     let extractPath = TEMP_PATH.join("ArchiveTemp").join(&archivePath.file_name().unwrap());
-    let archiveFS = ArchiveFS::new(&*archivePath, &extractPath, None, 1024, false, archiveFileInfoList, true, "ArchiveMount", true);
+    let overlayPath = TEMP_PATH.join("ArchiveOverlay").join(&archivePath.file_name().unwrap());
+    let layers = vec![ArchiveLayer::new(&zip, &archivePath, None, &extractPath).unwrap()];
+    let archiveFS = ArchiveFS::new(zip, layers, Overlay::new(&overlayPath).unwrap(), 1024, false, true, "ArchiveMount", true);
     archiveFS.mount("Z:".as_ref(), 0);
 }
 
@@ -91,3 +110,616 @@ fn getFileTree(list: &Vec<ArchiveFileInfo>) -> Vec<FileTree> {
     }
     return FileTree;
 }
+
+#[test]
+fn test_overlayWhiteout() {
+    let overlayPath = TEMP_PATH.join("ArchiveOverlay").join("test_overlayWhiteout");
+    let overlay = Overlay::new(&overlayPath).unwrap();
+    overlay.addWhiteout(r"dir\sub");
+    assert!(overlay.isDeleted(r"dir\sub"));
+    assert!(overlay.isDeleted(r"DIR\Sub\file.txt"));
+    assert!(!overlay.isDeleted(r"dir\subfile.txt"));
+
+    // 删除记录在重新打开后保留
+    let overlay = Overlay::new(&overlayPath).unwrap();
+    assert!(overlay.isDeleted("dir/sub/file.txt"));
+    assert!(overlay.removeWhiteout(r"dir\sub"));
+    assert!(!overlay.isDeleted(r"dir\sub\file.txt"));
+    fs::remove_dir_all(&overlayPath).ok();
+}
+
+/// 文件信息
+fn newFileInfo(path: &str, size: u64, is_dir: bool) -> ArchiveFileInfo {
+    ArchiveFileInfo { Path: path.to_string(), Size: size, PackedSize: size, Modified: "".to_string(), Created: None, is_dir, Layer: 0, CRC: "".to_string() }
+}
+
+/// 由文件列表生成已读取的压缩包层(不读取压缩包)
+fn newLayer(archivePath: &Path, extractPath: &Path, archiveFileInfoList: Vec<ArchiveFileInfo>) -> ArchiveLayer {
+    ArchiveLayer {
+        archivePath: archivePath.to_path_buf(),
+        volumeList: vec![archivePath.to_path_buf()],
+        password: None,
+        extractPath: extractPath.to_path_buf(),
+        archiveFileInfoList,
+        deletedList: Vec::new(),
+        prefix: String::new(),
+        indexed: true,
+        source: None,
+    }
+}
+
+#[test]
+fn test_writableFile() {
+    let testPath = TEMP_PATH.join("test_writableFile");
+    fs::remove_dir_all(&testPath).ok();
+    let layer = newLayer(&testPath.join("test.7z"), &testPath.join("extract"), vec![newFileInfo("dir", 0, true), newFileInfo(r"dir\a.txt", 1, false)]);
+    let overlay = Overlay::new(&testPath.join("overlay")).unwrap();
+    let upperFilePath = overlay.getUpperFilePath("new.txt");
+    let archiveFS = ArchiveFS::new(sevenZip::new().unwrap(), vec![layer], overlay, 1024, false, false, "ArchiveMount", false);
+    let fileName = |path: &str| U16CString::from_str(path).unwrap();
+    let isStatus = |result: Result<(), OperationError>, status: i32| matches!(result, Err(OperationError::NtStatus(code)) if code == status);
+
+    // 在偏移处写入(空隙填充 0)、追加写入，分页IO不扩展文件
+    let context = archiveFS.createFile(&fileName(r"\new.txt"), FILE_CREATE, false).unwrap().context.unwrap();
+    assert_eq!(archiveFS.writeFile("new.txt", &context, 4, b"abc", false, false).unwrap(), 3);
+    assert_eq!(archiveFS.writeFile("new.txt", &context, 0, b"12", false, false).unwrap(), 2);
+    assert_eq!(archiveFS.writeFile("new.txt", &context, -1, b"!", true, false).unwrap(), 1);
+    assert_eq!(archiveFS.writeFile("new.txt", &context, 7, b"xyz", false, true).unwrap(), 1);
+    assert_eq!(fs::read(&upperFilePath).unwrap(), b"12\0\0abcx");
+    assert!(archiveFS.writeFile("new.txt", &context, -1, b"!", false, false).is_err());
+
+    // 截断、扩展，预分配不改变文件大小
+    archiveFS.setFileSize("new.txt", &context, 2).unwrap();
+    assert_eq!(fs::read(&upperFilePath).unwrap(), b"12");
+    archiveFS.setFileSize("new.txt", &context, 4).unwrap();
+    assert_eq!(fs::read(&upperFilePath).unwrap(), b"12\0\0");
+    archiveFS.setAllocationSize("new.txt", &context, 1).unwrap();
+    archiveFS.setAllocationSize("new.txt", &context, 4096).unwrap();
+    assert_eq!(fs::read(&upperFilePath).unwrap(), b"1");
+    assert!(isStatus(archiveFS.setFileSize("new.txt", &context, -1), STATUS_INVALID_PARAMETER));
+    assert!(isStatus(archiveFS.setAllocationSize("new.txt", &context, -1), STATUS_INVALID_PARAMETER));
+
+    // 仅可删除空目录，删除后不可打开
+    assert!(isStatus(archiveFS.checkDeleteDirectory("dir"), STATUS_DIRECTORY_NOT_EMPTY));
+    archiveFS.removeEntry(r"dir\a.txt");
+    archiveFS.checkDeleteDirectory("dir").unwrap();
+    archiveFS.removeEntry("dir");
+    assert!(archiveFS.createFile(&fileName(r"\dir"), FILE_OPEN, true).is_err());
+    fs::remove_dir_all(&testPath).ok();
+}
+
+#[test]
+fn test_overlayCommit() {
+    let zip = sevenZip::new().unwrap();
+    let testPath = TEMP_PATH.join("test_overlayCommit");
+    fs::remove_dir_all(&testPath).ok();
+    let sourcePath = testPath.join("source");
+    fs::create_dir_all(sourcePath.join("dir")).unwrap();
+    fs::write(sourcePath.join("a.txt"), "1").unwrap();
+    fs::write(sourcePath.join("b.txt"), "2").unwrap();
+    fs::write(sourcePath.join(r"dir\c.txt"), "3").unwrap();
+    // 加密文件列表的压缩包
+    let archivePath = testPath.join("test.7z");
+    assert!(zip.updateArchiveFile(&archivePath, &sourcePath, Some("123"), true).unwrap());
+    assert!(zip.isHeaderEncrypted(&archivePath).unwrap());
+
+    // 修改 a.txt、新增 new.txt、删除 b.txt，未修改的 dir\c.txt 保留
+    let overlay = Overlay::new(&testPath.join("overlay")).unwrap();
+    fs::write(overlay.getUpperFilePath("a.txt"), "modified").unwrap();
+    fs::write(overlay.getUpperFilePath("new.txt"), "4").unwrap();
+    overlay.addWhiteout("b.txt");
+    overlay.commit(&zip, &archivePath, Some("123")).unwrap();
+    assert!(zip.isHeaderEncrypted(&archivePath).unwrap());
+    let list = zip.listArchiveFiles(&archivePath, Some("123")).unwrap();
+    let getSize = |path: &str| list.iter().find(|item| item.Path == path).map(|item| item.Size);
+    assert_eq!(getSize("a.txt"), Some(8));
+    assert_eq!(getSize("new.txt"), Some(1));
+    assert_eq!(getSize(r"dir\c.txt"), Some(1));
+    assert_eq!(getSize("b.txt"), None);
+    // 提交后清空覆盖层，再次提交不做修改
+    assert!(!overlay.hasChanges() && overlay.whiteoutList().is_empty());
+    overlay.commit(&zip, &archivePath, Some("123")).unwrap();
+
+    // 不支持更新的格式
+    let gzipSourcePath = testPath.join("gzip");
+    fs::create_dir_all(&gzipSourcePath).unwrap();
+    fs::write(gzipSourcePath.join("a.txt"), "1").unwrap();
+    let gzipPath = testPath.join("test.gz");
+    assert!(zip.updateArchiveFile(&gzipPath, &gzipSourcePath, None, false).unwrap());
+    fs::write(overlay.getUpperFilePath("a.txt"), "5").unwrap();
+    assert!(overlay.commit(&zip, &gzipPath, None).is_err());
+    assert!(overlay.hasChanges());
+    fs::remove_dir_all(&testPath).ok();
+}
+
+
+#[test]
+fn test_persistentOverlay() {
+    let overlayPath = TEMP_PATH.join("ArchiveOverlay").join("test_persistentOverlay");
+    let overlay = Overlay::newPersistent(&overlayPath, Path::new(r"D:\Archive.7z")).unwrap();
+    assert!(overlay.isPersistent());
+    // 同一压缩包可再次打开，其他压缩包不可使用
+    assert!(Overlay::newPersistent(&overlayPath, Path::new(r"d:\archive.7z")).is_ok());
+    assert!(Overlay::newPersistent(&overlayPath, Path::new(r"D:\Other.7z")).is_err());
+    fs::remove_dir_all(&overlayPath).ok();
+}
+
+#[test]
+fn test_overlaySnapshot() {
+    let overlayPath = TEMP_PATH.join("ArchiveOverlay").join("test_overlaySnapshot");
+    let overlay = Overlay::new(&overlayPath).unwrap();
+    fs::write(overlay.getUpperFilePath("a.txt"), "1").unwrap();
+    overlay.addWhiteout("b.txt");
+    overlay.createSnapshot("first").unwrap();
+    assert!(overlay.createSnapshot("first").is_err());
+
+    fs::write(overlay.getUpperFilePath("a.txt"), "2").unwrap();
+    overlay.addWhiteout("c.txt");
+    overlay.rollback(Some("first")).unwrap();
+    assert_eq!(fs::read_to_string(overlay.getUpperFilePath("a.txt")).unwrap(), "1");
+    assert!(overlay.isDeleted("b.txt") && !overlay.isDeleted("c.txt"));
+
+    // 回滚至初始状态
+    overlay.rollback(None).unwrap();
+    assert!(!overlay.hasChanges());
+    assert_eq!(overlay.listSnapshots().len(), 1);
+    fs::remove_dir_all(&overlayPath).ok();
+}
+
+#[test]
+fn test_mergeLayers() {
+    let layer = |archiveFileInfoList: Vec<ArchiveFileInfo>, deletedList: Vec<&str>| ArchiveLayer {
+        deletedList: deletedList.iter().map(|path| path.to_string()).collect(),
+        ..newLayer(Path::new(""), Path::new(""), archiveFileInfoList)
+    };
+    let layers = vec![
+        layer(vec![newFileInfo("a.txt", 1, false), newFileInfo("dir", 0, true), newFileInfo(r"dir\b.txt", 1, false)], vec![]),
+        layer(vec![newFileInfo("A.txt", 2, false), newFileInfo("c.txt", 1, false)], vec!["DIR"]),
+    ];
+    let list = mergeLayers(&layers);
+    assert_eq!(list.len(), 2);
+    // 上层覆盖下层同名文件
+    assert!(list.iter().any(|item| item.Path == "A.txt" && item.Size == 2 && item.Layer == 1));
+    // 删除清单移除目录及其子项
+    assert!(!list.iter().any(|item| item.Path.to_lowercase().starts_with("dir")));
+
+    // 嵌套压缩包显示为目录
+    let mut layers = vec![layer(vec![newFileInfo("a.zip", 1, false), newFileInfo("dir", 0, true), newFileInfo(r"dir\b.tar.gz", 1, false), newFileInfo("c.txt", 1, false)], vec![])];
+    assert!(listNestedLayers(&layers, 0, 0).is_empty());
+    let nestedLayers = listNestedLayers(&layers, 0, 1);
+    assert_eq!(nestedLayers.iter().map(|layer| layer.prefix.as_str()).collect::<Vec<&str>>(), vec!["a.zip", r"dir\b.tar.gz"]);
+    layers.extend(nestedLayers);
+    assert!(listNestedLayers(&layers, 0, 1).is_empty());
+    // 超过最大层数的嵌套压缩包不展开
+    layers[1].indexed = true;
+    layers[1].archiveFileInfoList = vec![newFileInfo("inner.7z", 1, false)];
+    assert!(listNestedLayers(&layers, 1, 1).is_empty());
+    let list = mergeLayers(&layers);
+    assert!(list.iter().any(|item| item.Path == "a.zip" && item.is_dir && item.Layer == 1));
+    assert!(list.iter().any(|item| item.Path == r"a.zip\inner.7z" && !item.is_dir));
+    assert!(list.iter().any(|item| item.Path == r"dir\b.tar.gz" && item.is_dir && item.Layer == 2));
+}
+
+#[test]
+fn test_archiveChange() {
+    // 状态变化后需两次检测一致(复制完成)才处理
+    let stateList = vec![1, 2, 3];
+    assert_eq!(getChangedLayers(&stateList, &stateList, &stateList), None);
+    assert_eq!(getChangedLayers(&stateList, &stateList, &[1, 5, 3]), None);
+    assert_eq!(getChangedLayers(&stateList, &[1, 4, 3], &[1, 5, 3]), None);
+    assert_eq!(getChangedLayers(&stateList, &[1, 5, 6], &[1, 5, 6]), Some(vec![1, 2]));
+
+    let testPath = TEMP_PATH.join("test_archiveChange");
+    fs::remove_dir_all(&testPath).ok();
+    let extractPath = testPath.join("extract");
+    let fileInfo = newFileInfo("a.txt", 1, false);
+    let localFilePath = extractPath.join("a.txt");
+    fs::create_dir_all(&testPath).unwrap();
+    fs::write(testPath.join("Game.7z"), "").unwrap();
+    let newArchiveFS = |archiveChangeMode: ArchiveChangeMode| {
+        let mut layer = newLayer(&testPath.join("Game.7z"), &extractPath, vec![fileInfo.clone()]);
+        layer.prefix = "Game".to_string();
+        let mut archiveFS = ArchiveFS::new(sevenZip::new().unwrap(), vec![layer], Overlay::new(&testPath.join("overlay")).unwrap(), 1024, true, false, "ArchiveMount", false);
+        archiveFS.setArchiveChangeMode(archiveChangeMode);
+        archiveFS
+    };
+
+    // 拒绝读取: 已缓存的文件也不可读取
+    let archiveFS = newArchiveFS(ArchiveChangeMode::refuse);
+    fs::write(&localFilePath, "1").unwrap();
+    assert!(archiveFS.extractFile(&fileInfo, &localFilePath).is_ok());
+    archiveFS.onArchiveChanged(&[0]).unwrap();
+    assert!(matches!(archiveFS.extractFile(&fileInfo, &localFilePath), Err(OperationError::NtStatus(code)) if code == STATUS_FILE_INVALID));
+    assert_eq!(archiveFS.fileList().len(), 2);
+
+    // 重新读取: 目录挂载中已删除的压缩包不再显示，且不再读取
+    let archiveFS = newArchiveFS(ArchiveChangeMode::reload);
+    assert_eq!(archiveFS.fileList().len(), 2);
+    fs::remove_file(testPath.join("Game.7z")).unwrap();
+    archiveFS.onArchiveChanged(&[0]).unwrap();
+    assert!(archiveFS.fileList().is_empty());
+    assert!(!archiveFS.layers()[0].indexed);
+    assert!(archiveFS.extractFile(&fileInfo, &localFilePath).is_ok());
+    fs::remove_dir_all(&testPath).ok();
+}
+
+#[test]
+fn test_rootPath() {
+    let testPath = TEMP_PATH.join("test_rootPath");
+    fs::remove_dir_all(&testPath).ok();
+    let newArchiveFS = || {
+        let fileList = vec![newFileInfo("Game-v1.2", 0, true), newFileInfo(r"Game-v1.2\bin", 0, true), newFileInfo(r"Game-v1.2\bin\a.exe", 1, false), newFileInfo(r"Game-v1.2\data", 0, true)];
+        let layer = newLayer(&testPath.join("test.7z"), &testPath.join("extract"), fileList);
+        ArchiveFS::new(sevenZip::new().unwrap(), vec![layer], Overlay::new(&testPath.join("overlay")).unwrap(), 1024, true, false, "ArchiveMount", false)
+    };
+
+    // 未设置根目录时路径不变
+    let mut archiveFS = newArchiveFS();
+    assert_eq!(archiveFS.getRelativePath(r"\Game-v1.2\bin"), r"Game-v1.2\bin");
+    assert_eq!(archiveFS.getMountedPath(r"Game-v1.2\bin"), Some(r"Game-v1.2\bin"));
+
+    // 去除一层唯一的顶层目录
+    archiveFS.setRootPath(None, 1).unwrap();
+    assert_eq!(archiveFS.getRelativePath("\\"), "Game-v1.2");
+    assert_eq!(archiveFS.getRelativePath(r"\bin\a.exe"), r"Game-v1.2\bin\a.exe");
+    assert_eq!(archiveFS.getMountedPath(r"game-v1.2\bin\a.exe"), Some(r"bin\a.exe"));
+    assert_eq!(archiveFS.getMountedPath(r"Game-v1.3\bin"), None);
+    // 第二层包含多个条目
+    assert!(newArchiveFS().setRootPath(None, 2).is_err());
+
+    // 指定根目录(不区分大小写，可使用 /)
+    let mut archiveFS = newArchiveFS();
+    archiveFS.setRootPath(Some("game-v1.2/BIN"), 0).unwrap();
+    assert_eq!(archiveFS.getRelativePath(r"\a.exe"), r"Game-v1.2\bin\a.exe");
+    assert!(newArchiveFS().setRootPath(Some("Game-v1.2/lib"), 0).is_err());
+    assert!(newArchiveFS().setRootPath(Some(r"Game-v1.2\bin\a.exe"), 0).is_err());
+
+    // 过滤规则匹配去除根目录后的路径，根目录及其上级目录不过滤
+    let pathList = |archiveFS: &ArchiveFS| archiveFS.fileList().iter().map(|item| item.Path.clone()).collect::<Vec<String>>();
+    let mut archiveFS = newArchiveFS();
+    archiveFS.setRootPath(None, 1).unwrap();
+    archiveFS.setEntryFilter(EntryFilter::new(&[], &["bin/a.exe".to_string()]).unwrap());
+    assert_eq!(pathList(&archiveFS), vec!["Game-v1.2", r"Game-v1.2\bin", r"Game-v1.2\data"]);
+    archiveFS.setEntryFilter(EntryFilter::new(&["bin/**".to_string()], &[]).unwrap());
+    assert_eq!(pathList(&archiveFS), vec!["Game-v1.2", r"Game-v1.2\bin", r"Game-v1.2\bin\a.exe"]);
+    // 先设置过滤规则时同样匹配挂载中的路径
+    let mut archiveFS = newArchiveFS();
+    archiveFS.setEntryFilter(EntryFilter::new(&["data".to_string()], &[]).unwrap());
+    archiveFS.setRootPath(Some("Game-v1.2"), 0).unwrap();
+    assert_eq!(pathList(&archiveFS), vec!["Game-v1.2", r"Game-v1.2\data"]);
+    fs::remove_dir_all(&testPath).ok();
+}
+
+#[test]
+fn test_commitOnUnmount() {
+    // 配置项及构建器参数
+    let mut profile: MountProfile = toml::from_str("cacheSize = 2048").unwrap();
+    profile.merge(&toml::from_str("commitOnUnmount = true").unwrap());
+    assert_eq!(profile.commitOnUnmount, Some(true));
+    let builder = MountBuilder::new("Game.7z", "Z:").profile(&profile).unwrap();
+    assert!(builder.options().commitOnUnmount);
+    assert!(!builder.commitOnUnmount(false).options().commitOnUnmount);
+    // 当前进程中没有挂载时立即返回
+    assert!(unmountActiveMounts(Duration::ZERO));
+
+    let zip = sevenZip::new().unwrap();
+    let testPath = TEMP_PATH.join("test_commitOnUnmount");
+    fs::remove_dir_all(&testPath).ok();
+    let sourcePath = testPath.join("source");
+    fs::create_dir_all(&sourcePath).unwrap();
+    fs::write(sourcePath.join("a.txt"), "1").unwrap();
+    let archivePath = testPath.join("test.7z");
+    assert!(zip.updateArchiveFile(&archivePath, &sourcePath, None, false).unwrap());
+    let layer = newLayer(&archivePath, &testPath.join("extract"), vec![newFileInfo("a.txt", 1, false)]);
+    let overlay = Overlay::new(&testPath.join("overlay")).unwrap();
+    fs::write(overlay.getUpperFilePath("new.txt"), "2").unwrap();
+    let mut archiveFS = ArchiveFS::new(zip, vec![layer], overlay, 1024, false, false, "ArchiveMount", false);
+
+    // 未启用时保留覆盖层的修改
+    assert!(!archiveFS.commitUnmountChanges());
+    archiveFS.setCommitOnUnmount(true);
+    assert!(archiveFS.commitUnmountChanges());
+    let list = sevenZip::new().unwrap().listArchiveFiles(&archivePath, None).unwrap();
+    assert!(list.iter().any(|item| item.Path == "a.txt") && list.iter().any(|item| item.Path == "new.txt"));
+    // 已提交后无修改
+    assert!(!archiveFS.commitUnmountChanges());
+    fs::remove_dir_all(&testPath).ok();
+}
+
+#[test]
+fn test_mountBuilder() {
+    let builder = MountBuilder::new("Game.7z", "Z:")
+        .password("123456")
+        .cacheSize(512)
+        .readOnly(true)
+        .patch("Patch.7z")
+        .include("bin/**")
+        .include("*.md")
+        .onArchiveChange(ArchiveChangeMode::refuse)
+        .idleTimeout(Duration::from_secs(60))
+        .root("Game")
+        .stripComponents(1);
+    let options = builder.options();
+    assert_eq!((options.archivePath.as_path(), options.mountPath.as_path()), (Path::new("Game.7z"), Path::new("Z:")));
+    assert_eq!(options.password.as_deref(), Some("123456"));
+    assert!(options.cacheSize == 512 && options.readOnly);
+    assert_eq!(options.patch, vec![PathBuf::from("Patch.7z")]);
+    assert_eq!(options.include, vec!["bin/**", "*.md"]);
+    assert_eq!(options.onArchiveChange, ArchiveChangeMode::refuse);
+    assert_eq!(options.idleTimeout, Some(Duration::from_secs(60)));
+    assert_eq!((options.root.as_deref(), options.stripComponents), (Some("Game"), 1));
+
+    // 配置项覆盖已设置的参数，未设置的项保留
+    let profile: MountProfile = toml::from_str("cacheSize = 2048\nidleTimeout = \"10m\"\nexclude = [\"*.log\"]").unwrap();
+    let builder = builder.profile(&profile).unwrap();
+    let options = builder.options();
+    assert_eq!(options.cacheSize, 2048);
+    assert_eq!(options.idleTimeout, Some(Duration::from_secs(600)));
+    assert_eq!(options.exclude, vec!["*.log"]);
+    assert!(options.readOnly && options.password.is_some());
+    let profile: MountProfile = toml::from_str("idleTimeout = \"soon\"").unwrap();
+    assert!(builder.clone().profile(&profile).is_err());
+
+    // 设置事件回调后事件由回调处理
+    assert!(builder.eventHandler().is_none());
+    let eventList = Arc::new(Mutex::new(Vec::new()));
+    let handlerList = eventList.clone();
+    let builder = builder.onEvent(move |event| handlerList.lock().unwrap().push(serde_json::to_string(event).unwrap()));
+    emitEvent(builder.eventHandler(), ConsoleEvent::new(ConsoleType::Warning, "test"));
+    assert_eq!(*eventList.lock().unwrap(), vec![r#"{"event":"warning","message":"test"}"#]);
+}
+
+#[test]
+fn test_archiveDirectory() {
+    let directoryPath = PathBuf::from("./test/archiveDirectory");
+    fs::create_dir_all(&directoryPath).unwrap();
+    for name in ["Game.7z", "Game.zip", "Tools.rar", "readme.txt"] {
+        fs::write(directoryPath.join(name), "").unwrap();
+    }
+    let archiveDirectory = ArchiveDirectory { path: directoryPath.clone(), password: None, extractPath: PathBuf::from("./test/none"), stripExtension: true };
+    let layers = archiveDirectory.listNewLayers(&[]);
+    let nameList: Vec<&str> = layers.iter().map(|layer| layer.prefix.as_str()).collect();
+    // 去除扩展名后重名时保留扩展名，非压缩包文件被忽略
+    assert_eq!(nameList, vec!["Game", "Game.zip", "Tools"]);
+    assert!(layers.iter().all(|layer| !layer.indexed));
+    assert_eq!(layers[2].getArchivePath(r"Tools\bin\a.exe"), r"bin\a.exe");
+    // 已加入的压缩包不再列出
+    assert!(archiveDirectory.listNewLayers(&layers).is_empty());
+    // 未读取的压缩包显示为空目录
+    let list = mergeLayers(&layers);
+    assert_eq!(list.len(), 3);
+    assert!(list.iter().all(|item| item.is_dir));
+    fs::remove_dir_all(&directoryPath).ok();
+}
+
+#[test]
+fn test_entryFilter() {
+    let fileList = vec![
+        newFileInfo("__MACOSX", 0, true), newFileInfo(r"__MACOSX\._a.txt", 1, false), newFileInfo("src", 0, true), newFileInfo(r"src\main.c", 1, false),
+        newFileInfo("bin", 0, true), newFileInfo(r"bin\app.exe", 1, false), newFileInfo(r"bin\.DS_Store", 1, false), newFileInfo("desktop.ini", 1, false),
+    ];
+    let pathList = |list: Vec<ArchiveFileInfo>| list.into_iter().map(|item| item.Path).collect::<Vec<String>>();
+    // 默认隐藏 desktop.ini 等条目
+    assert_eq!(pathList(EntryFilter::default().apply(fileList.clone(), &[])).len(), 7);
+    // 排除目录时同时隐藏其子项
+    let entryFilter = EntryFilter::new(&[], &["__MACOSX/".to_string(), ".DS_Store".to_string()]).unwrap();
+    assert_eq!(pathList(entryFilter.apply(fileList.clone(), &[])), vec!["src", r"src\main.c", "bin", r"bin\app.exe"]);
+    assert!(entryFilter.isExcluded(r"__macosx\new.txt"));
+    // 仅默认隐藏的条目不可创建
+    assert!(!entryFilter.isSystemEntry(r"bin\.DS_Store") && entryFilter.isSystemEntry(r"$Recycle.Bin\S-1-5"));
+    // 包含模式保留匹配条目的上级目录
+    let entryFilter = EntryFilter::new(&["*.exe".to_string()], &[]).unwrap();
+    assert_eq!(pathList(entryFilter.apply(fileList.clone(), &["src".to_string()])), vec!["src", "bin", r"bin\app.exe"]);
+    assert!(EntryFilter::new(&["[".to_string()], &[]).is_err());
+}
+
+#[test]
+fn test_volumeSet() {
+    let directoryPath = PathBuf::from("./test/volumeSet");
+    fs::create_dir_all(&directoryPath).unwrap();
+    for name in ["Game.7z.001", "Game.7z.002", "Game.7z.003", "Tools.part1.rar", "Tools.part3.rar", "Data.z01", "Data.z02", "Data.zip", "Single.7z"] {
+        fs::write(directoryPath.join(name), "").unwrap();
+    }
+    // 由任意分卷识别整个分卷压缩包
+    let volumeSet = resolveVolumes(&directoryPath.join("Game.7z.002")).unwrap();
+    assert_eq!(volumeSet.name, "Game.7z");
+    assert_eq!(volumeSet.primaryPath, directoryPath.join("Game.7z.001"));
+    assert_eq!(volumeSet.volumeList.len(), 3);
+    // zip 分卷由最后一卷(.zip)打开
+    let volumeSet = resolveVolumes(&directoryPath.join("Data.z01")).unwrap();
+    assert_eq!(volumeSet.primaryPath, directoryPath.join("Data.zip"));
+    assert_eq!(resolveVolumes(&directoryPath.join("Data.zip")).unwrap(), volumeSet);
+    // 缺少分卷时列出缺少的分卷
+    let err = resolveVolumes(&directoryPath.join("Tools.part3.rar")).unwrap_err();
+    assert!(err.to_string().contains("Tools.part2.rar"));
+    assert_eq!(resolveVolumes(&directoryPath.join("Single.7z")).unwrap().volumeList.len(), 1);
+    assert_eq!(parseVolumeFileName("Tools.part01.rar"), Some(("Tools.rar".to_string(), true)));
+    assert_eq!(parseVolumeFileName("Data.z02"), Some(("Data.zip".to_string(), false)));
+    assert_eq!(parseVolumeFileName("Game.rar"), None);
+    // 目录挂载中未读取的分卷压缩包同样监视所有分卷
+    let layer = ArchiveLayer::unindexed(&directoryPath.join("Game.7z.001"), None, Path::new("./test/none"), "Game.7z");
+    assert_eq!(layer.volumeList.len(), 3);
+
+    // 缺少末尾的分卷时编号连续，由 7-Zip 的输出判断
+    let volumeList = vec![directoryPath.join("Game.7z.001"), directoryPath.join("Game.7z.002")];
+    assert!(checkVolumes("Type = Split\r\nVolumes = 2\r\n", &volumeList).is_ok());
+    assert!(checkVolumes("ERRORS:\r\nUnexpected end of archive\r\n", &volumeList).unwrap_err().to_string().contains("after Game.7z.002"));
+    assert!(checkVolumes("Type = Rar5\r\nVolumes = 3\r\n", &volumeList).is_err());
+    assert!(checkVolumes("ERROR: Missing volume : Tools.part3.rar\r\n", &volumeList).unwrap_err().to_string().contains("Tools.part3.rar"));
+    fs::remove_dir_all(&directoryPath).ok();
+}
+
+#[test]
+fn test_parseEvent() {
+    assert!(matches!(ConsoleEvent::parse(r#"{"event":"error","code":11,"message":"Drive letter error"}"#), ConsoleEvent::error { code: 11, .. }));
+    // 无法解析的行视为普通信息
+    assert!(matches!(ConsoleEvent::parse("  Info      text"), ConsoleEvent::info { message } if message == "Info      text"));
+}
+
+#[test]
+fn test_writeRules() {
+    let writeRules = WriteRules::parse("# 存档\npersist saves/**\ndeny *.exe\ndiscard *.ini\npersist *.ini").unwrap();
+    assert_eq!(writeRules.getPolicy(r"Saves\slot1.sav"), Some(WritePolicy::Persist));
+    // 不含 / 的模式匹配任意目录下的文件名
+    assert_eq!(writeRules.getPolicy(r"bin\Game.EXE"), Some(WritePolicy::Deny));
+    // 首条匹配的规则生效
+    assert_eq!(writeRules.getPolicy("config.ini"), Some(WritePolicy::Discard));
+    assert_eq!(writeRules.getPolicy(r"data\level.pak"), None);
+    assert!(WriteRules::parse("keep *.txt").is_err());
+}
+
+#[test]
+fn test_managerRequest() {
+    let content = serde_json::to_string(&ManagerRequest::unmount { mountPath: PathBuf::from("Z:") }).unwrap();
+    assert_eq!(content, r#"{"command":"unmount","mountPath":"Z:"}"#);
+    assert!(matches!(serde_json::from_str(r#"{"command":"status"}"#).unwrap(), ManagerRequest::status));
+}
+
+#[test]
+fn test_mountStatus() {
+    // 旧版本的挂载记录不含挂载时间等字段
+    let record: MountRecord = serde_json::from_str(r#"{"pid":1,"archivePath":"a.7z","mountPath":"Z:","extractPath":"./test/none","overlayPath":"","readOnly":true}"#).unwrap();
+    assert!(!record.persistentOverlay && record.mountedAt == 0);
+    let status = MountStatus::fromRecord(record);
+    assert!(!status.managed && status.openHandles.is_none());
+    assert_eq!((status.uptime, status.cacheUsage), (0, 0));
+}
+
+#[test]
+fn test_consoleEvent() {
+    let content = serde_json::to_string(&ConsoleEvent::error { code: 15, message: "The mount point is invalid".to_string() }).unwrap();
+    assert_eq!(content, r#"{"event":"error","code":15,"message":"The mount point is invalid"}"#);
+    let content = serde_json::to_string(&ConsoleEvent::mounted { mountPath: "Z:".to_string(), message: "".to_string() }).unwrap();
+    assert!(content.starts_with(r#"{"event":"mounted","mountPath":"Z:""#));
+}
+
+#[test]
+fn test_mountConfig() {
+    let config = MountConfig::parse(r#"
+        [default]
+        cacheSize = 1024
+        threadCount = 2
+
+        [profiles.game]
+        match = "D:/Games/*.7z"
+        readOnly = true
+        password = "123456"
+
+        [profiles.fast]
+        cacheSize = 8192
+        idleTimeout = "10m"
+    "#).unwrap();
+    // 全局默认值 < 匹配的配置 < 指定的配置
+    let profile = config.resolve(Path::new(r"D:\Games\Game.7z"), Some("fast")).unwrap();
+    assert_eq!((profile.cacheSize, profile.threadCount, profile.readOnly), (Some(8192), Some(2), Some(true)));
+    assert!(config.resolve(Path::new("Other.7z"), None).unwrap().readOnly.is_none());
+    assert!(config.resolve(Path::new("Other.7z"), Some("none")).is_err());
+
+    // 命令行中指定的参数不被覆盖
+    let mut options = MountOptions::new(Path::new("Game.7z"), Path::new("Z:"));
+    options.threadCount = 8;
+    options.applyProfile(&profile, |id| id == "threadCount").unwrap();
+    assert_eq!((options.cacheSize, options.threadCount, options.password.as_deref()), (8192, 8, Some("123456")));
+    assert_eq!(options.idleTimeout, Some(Duration::from_secs(600)));
+    assert!(MountConfig::parse("[default]\ncache = 1").is_err());
+}
+
+#[test]
+fn test_mountManifest() {
+    let manifest = MountManifest::parse(r#"
+        [[mount]]
+        archivePath = 'D:\Game.7z'
+        mountPath = 'Z:'
+        profile = "game"
+
+        [[mount]]
+        archivePath = 'D:\DLC.7z'
+        mountPath = 'Y:'
+        readOnly = true
+    "#).unwrap();
+    assert_eq!(manifest.mountList.len(), 2);
+    assert_eq!(manifest.mountList[0].profile.as_deref(), Some("game"));
+    assert_eq!(manifest.mountList[1].options.readOnly, Some(true));
+    assert!(MountManifest::parse("mount = []").is_err());
+}
+
+#[test]
+fn test_staleMountCleanup() {
+    // 挂载进程退出后遗留的缓存目录及挂载记录
+    let extractPath = TEMP_PATH.join("ArchiveTemp").join("test_stale.7z");
+    let patchExtractPath = TEMP_PATH.join("ArchiveTemp").join("test_stale.7z-patch1");
+    fs::create_dir_all(&extractPath).unwrap();
+    fs::create_dir_all(&patchExtractPath).unwrap();
+    let mountPath = TEMP_PATH.join("test_staleMountCleanup");
+    let record: MountRecord = serde_json::from_str(r#"{"pid":1,"archivePath":"test_stale.7z","mountPath":"","extractPath":"","overlayPath":"","readOnly":true}"#).unwrap();
+    let record = MountRecord { mountPath: mountPath.clone(), extractPath: extractPath.clone(), cachePathList: vec![patchExtractPath.clone()], ..record };
+    drop(record.register().unwrap());
+    record.cleanup();
+    assert!(!extractPath.exists() && !patchExtractPath.exists());
+    assert!(findMountRecord(&mountPath).is_none());
+}
+
+#[test]
+fn test_commitRequest() {
+    let mountPath = TEMP_PATH.join("test_commitRequest");
+    let record: MountRecord = serde_json::from_str(r#"{"pid":1,"archivePath":"test.7z","mountPath":"","extractPath":"","overlayPath":"","readOnly":false}"#).unwrap();
+    let record = MountRecord { mountPath: mountPath.clone(), ..record };
+    // 旧版本的挂载记录不支持提交
+    assert!(!record.commitSupported);
+    let lock = record.register().unwrap();
+    // 挂载锁被持有时等待超时
+    assert!(!record.waitReleased(Duration::ZERO));
+    record.requestCommit().unwrap();
+    assert!(isCommitRequested(&mountPath));
+    drop(lock);
+    assert!(record.waitReleased(Duration::ZERO));
+    record.cancelCommit();
+    assert!(!isCommitRequested(&mountPath));
+    record.unregister();
+}
+
+#[test]
+fn test_pathHash() {
+    // 不同目录下的同名压缩包使用不同的缓存目录
+    assert_ne!(getPathHash(Path::new(r"D:\a\data.7z")), getPathHash(Path::new(r"D:\b\data.7z")));
+    assert_eq!(getPathHash(Path::new(r"D:\a\data.7z")), getPathHash(Path::new(r"d:\A\Data.7z")));
+    assert_eq!(getPathHash(Path::new("")), "cbf29ce484222325");
+}
+
+#[test]
+fn test_parseDuration() {
+    assert_eq!(parseDuration("90").unwrap(), Duration::from_secs(90));
+    assert_eq!(parseDuration("30s").unwrap(), Duration::from_secs(30));
+    assert_eq!(parseDuration(" 15M ").unwrap(), Duration::from_secs(900));
+    assert_eq!(parseDuration("2h").unwrap(), Duration::from_secs(7200));
+    assert_eq!(parseDuration("7d").unwrap(), Duration::from_secs(604800));
+    assert!(parseDuration("").is_err());
+    assert!(parseDuration("-5m").is_err());
+    assert!(parseDuration("10w").is_err());
+    // 换算为秒时溢出
+    assert_eq!(parseDuration("18446744073709551615d").unwrap_err().to_string(), "Invalid duration: 18446744073709551615d");
+    assert!(parseDuration("18446744073709551615s").is_ok());
+}
+
+#[test]
+fn test_stackedPaths() {
+    let basePath = TEMP_PATH.join("test_base.7z");
+    let dlcPath = TEMP_PATH.join("test_dlc.7z");
+    fs::create_dir_all(&*TEMP_PATH).unwrap();
+    fs::write(&basePath, "").unwrap();
+    fs::write(&dlcPath, "").unwrap();
+    // mount base.7z dlc.7z Z: D:\Temp
+    let mut options = MountOptions::new(&basePath, &dlcPath);
+    options.tempPath = Some(PathBuf::from("Z:"));
+    options.stackedPaths = vec![PathBuf::from(r"D:\Temp")];
+    options.resolveStackedPaths().unwrap();
+    assert_eq!(options.stackedPaths, vec![dlcPath.clone()]);
+    assert_eq!((options.mountPath.to_str(), options.tempPath.as_deref().and_then(Path::to_str)), (Some("Z:"), Some(r"D:\Temp")));
+    // mount base.7z Z: D:\Temp 不变
+    let mut options = MountOptions::new(&basePath, Path::new("Z:"));
+    options.tempPath = Some(PathBuf::from(r"D:\Temp"));
+    options.resolveStackedPaths().unwrap();
+    assert!(options.stackedPaths.is_empty() && options.tempPath.is_some());
+    fs::remove_file(&basePath).ok();
+    fs::remove_file(&dlcPath).ok();
+}
@@ -1,18 +1,138 @@
+use std::fmt;
+use std::io::{self, Write};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicI32, Ordering};
+
+use clap::ArgEnum;
 use console::style;
+use serde::{Deserialize, Serialize};
+
+/// 一般错误的退出码
+pub const EXIT_ERROR: i32 = 1;
+
+/// 是否以 JSON 输出
+static JSON_OUTPUT: AtomicBool = AtomicBool::new(false);
+/// 进程退出码(输出错误时设置)
+static EXIT_CODE: AtomicI32 = AtomicI32::new(0);
 
 pub enum ConsoleType {
     Info,
+    /// 进度信息(文本输出与 Info 相同)
+    Progress,
     Success,
     Warning,
     Err,
 }
 
+/// 输出格式
+#[derive(ArgEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// 彩色文本
+    text,
+    /// 每行一个 JSON 事件
+    json,
+}
+
+/// 输出事件(JSON 输出时每行一个)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "event")]
+pub enum ConsoleEvent {
+    info { message: String },
+    progress { message: String },
+    success { message: String },
+    warning { message: String },
+    /// 挂载完成
+    mounted { mountPath: String, message: String },
+    /// 错误(code 与进程退出码一致)
+    error { code: i32, message: String },
+}
+
+impl ConsoleEvent {
+    pub fn new(consoleType: ConsoleType, message: &str) -> ConsoleEvent {
+        let message = message.to_string();
+        match consoleType {
+            ConsoleType::Info => ConsoleEvent::info { message },
+            ConsoleType::Progress => ConsoleEvent::progress { message },
+            ConsoleType::Success => ConsoleEvent::success { message },
+            ConsoleType::Warning => ConsoleEvent::warning { message },
+            ConsoleType::Err => ConsoleEvent::error { code: EXIT_ERROR, message },
+        }
+    }
+
+    /// 解析 JSON 输出的事件行(无法解析的行视为普通信息)
+    pub fn parse(line: &str) -> ConsoleEvent {
+        serde_json::from_str(line).unwrap_or_else(|_| ConsoleEvent::info { message: line.trim().to_string() })
+    }
+}
+
+/// 事件回调(设置后挂载的事件不再输出至控制台)
+#[derive(Clone)]
+pub struct EventHandler(Arc<dyn Fn(&ConsoleEvent) + Send + Sync>);
+
+impl EventHandler {
+    pub fn new(handler: impl Fn(&ConsoleEvent) + Send + Sync + 'static) -> EventHandler {
+        EventHandler(Arc::new(handler))
+    }
+}
+
+impl fmt::Debug for EventHandler {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("EventHandler")
+    }
+}
+
+/// 设置输出格式
+pub fn setOutputFormat(outputFormat: OutputFormat) {
+    JSON_OUTPUT.store(outputFormat == OutputFormat::json, Ordering::SeqCst);
+}
+
+/// 是否以 JSON 输出
+pub fn isJsonOutput() -> bool {
+    JSON_OUTPUT.load(Ordering::SeqCst)
+}
+
+/// 设置进程退出码
+pub fn setExitCode(code: i32) {
+    EXIT_CODE.store(code, Ordering::SeqCst);
+}
+
+/// 进程退出码
+pub fn getExitCode() -> i32 {
+    EXIT_CODE.load(Ordering::SeqCst)
+}
+
 pub fn writeConsole(consoleType: ConsoleType, message: &str) {
-    let title = match &consoleType {
-        ConsoleType::Info => style("Info   ").cyan(),
-        ConsoleType::Success => style("Success").green(),
-        ConsoleType::Warning => style("Warning").yellow(),
-        ConsoleType::Err => style("Err    ").red(),
+    writeEvent(ConsoleEvent::new(consoleType, message));
+}
+
+/// 输出错误并设置进程退出码
+pub fn writeError(code: i32, message: &str) {
+    writeEvent(ConsoleEvent::error { code, message: message.to_string() });
+}
+
+/// 输出事件至事件回调(未设置时输出至控制台)
+pub fn emitEvent(eventHandler: Option<&EventHandler>, event: ConsoleEvent) {
+    match eventHandler {
+        Some(eventHandler) => (eventHandler.0)(&event),
+        None => writeEvent(event),
+    }
+}
+
+/// 输出事件
+/// 忽略写入错误: 挂载后调用方(如 CLI 包装程序)可能已关闭标准输出，此时 println! 会 panic 并导致进程退出
+pub fn writeEvent(event: ConsoleEvent) {
+    if let ConsoleEvent::error { code, .. } = &event {
+        EXIT_CODE.store(*code, Ordering::SeqCst);
+    }
+    if isJsonOutput() {
+        writeln!(io::stdout(), "{}", serde_json::to_string(&event).unwrap()).ok();
+        return;
+    }
+    let (title, message) = match &event {
+        ConsoleEvent::info { message } | ConsoleEvent::progress { message } => (style("Info   ").cyan(), message),
+        ConsoleEvent::success { message } | ConsoleEvent::mounted { message, .. } => (style("Success").green(), message),
+        ConsoleEvent::warning { message } => (style("Warning").yellow(), message),
+        ConsoleEvent::error { message, .. } => (style("Err    ").red(), message),
     };
-    println!("  {}      {}", &title, message);
+    writeln!(io::stdout(), "  {}      {}", &title, message).ok();
 }
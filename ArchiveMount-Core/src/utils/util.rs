@@ -1,14 +1,17 @@
+use std::env;
 use std::error::Error;
 use std::ffi::OsStr;
+use std::fs;
 use std::fs::File;
 use std::io::Write;
 use std::iter::once;
 use std::os::windows::ffi::OsStrExt;
-use std::path::Path;
+use std::os::windows::io::AsRawHandle;
+use std::path::{Path, PathBuf};
 use std::ptr::null;
-use std::time::SystemTime;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
-use anyhow::Result;
+use anyhow::{anyhow, bail, Result};
 use chrono::{DateTime, Local, NaiveDateTime, TimeZone};
 
 use crate::Asset;
@@ -20,6 +23,91 @@ pub fn writeEmbedFile(filePath: &str, outFilePath: &Path) -> Result<()> {
     Ok(())
 }
 
+/// 替换文件(原文件先重命名备份，替换失败时还原)
+/// 参数1: 新文件路径
+/// 参数2: 被替换的文件路径
+pub fn replaceFile(newFilePath: &Path, filePath: &Path) -> Result<()> {
+    let backupPath = filePath.with_file_name(format!("~backup-{}", filePath.file_name().unwrap().to_str().unwrap()));
+    fs::rename(filePath, &backupPath)?;
+    if let Err(err) = fs::rename(newFilePath, filePath) {
+        fs::rename(&backupPath, filePath).ok();
+        return Err(err.into());
+    }
+    fs::remove_file(&backupPath).ok();
+    Ok(())
+}
+
+/// 复制目录(递归复制所有子项，目标目录不存在时创建)
+/// 参数1: 源目录
+/// 参数2: 目标目录
+pub fn copyDirectory(sourcePath: &Path, targetPath: &Path) -> Result<()> {
+    fs::create_dir_all(targetPath)?;
+    for entry in sourcePath.read_dir()? {
+        let entry = entry?;
+        let targetFilePath = targetPath.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            copyDirectory(&entry.path(), &targetFilePath)?;
+        } else {
+            fs::copy(entry.path(), &targetFilePath)?;
+        }
+    }
+    Ok(())
+}
+
+/// 获取目录大小(递归统计所有文件，目录不存在时返回 0)
+pub fn getDirectorySize(path: &Path) -> u64 {
+    let mut size = 0;
+    if let Ok(entries) = path.read_dir() {
+        for entry in entries.flatten() {
+            match entry.file_type() {
+                Ok(fileType) if fileType.is_dir() => size += getDirectorySize(&entry.path()),
+                Ok(_) => size += entry.metadata().map(|metadata| metadata.len()).unwrap_or(0),
+                Err(_) => {}
+            }
+        }
+    }
+    size
+}
+
+/// 当前时间(UNIX时间戳，单位: 秒)
+pub fn getUnixTimestamp() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|duration| duration.as_secs()).unwrap_or(0)
+}
+
+/// 解析时长(单位后缀: s 秒、m 分、h 时、d 天，无后缀为秒)
+pub fn parseDuration(text: &str) -> Result<Duration> {
+    let text = text.trim();
+    let (number, unit) = match text.find(|c: char| !c.is_ascii_digit()) {
+        Some(index) => text.split_at(index),
+        None => (text, "s"),
+    };
+    let number: u64 = number.parse().map_err(|_| anyhow!("Invalid duration: {}", text))?;
+    let multiplier: u64 = match unit.trim().to_lowercase().as_str() {
+        "s" => 1,
+        "m" => 60,
+        "h" => 3600,
+        "d" => 86400,
+        _ => bail!("Invalid duration unit: {}", text),
+    };
+    let seconds = number.checked_mul(multiplier).ok_or_else(|| anyhow!("Invalid duration: {}", text))?;
+    Ok(Duration::from_secs(seconds))
+}
+
+/// 获取绝对路径(相对路径基于当前工作目录)
+pub fn getAbsolutePath(path: &Path) -> PathBuf {
+    if path.is_relative() { env::current_dir().unwrap().join(path) } else { path.to_path_buf() }
+}
+
+/// 路径的哈希值(FNV-1a，不区分大小写)，用于生成不重复的目录名
+pub fn getPathHash(path: &Path) -> String {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for byte in path.to_string_lossy().to_lowercase().bytes() {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    format!("{:016x}", hash)
+}
+
 /// 创建虚拟盘符
 /// 参数1: 目标路径
 /// 参数2: 挂载盘符
@@ -38,6 +126,35 @@ pub fn removeVirtualDrive(mountPath: &Path) -> bool {
     result != 0
 }
 
+/// 为文件预分配磁盘空间(不改变文件大小)
+pub fn preallocateFile(file: &File, size: u64) -> bool {
+    let mut information = winapi::um::fileapi::FILE_ALLOCATION_INFO { AllocationSize: unsafe { std::mem::zeroed() } };
+    unsafe { *information.AllocationSize.QuadPart_mut() = size as i64 };
+    let result = unsafe {
+        winapi::um::fileapi::SetFileInformationByHandle(
+            file.as_raw_handle() as _,
+            winapi::um::minwinbase::FileAllocationInfo,
+            &mut information as *mut _ as _,
+            std::mem::size_of::<winapi::um::fileapi::FILE_ALLOCATION_INFO>() as u32,
+        )
+    };
+    result != 0
+}
+
+/// 进程是否正在运行
+pub fn isProcessRunning(pid: u32) -> bool {
+    unsafe {
+        let handle = winapi::um::processthreadsapi::OpenProcess(winapi::um::winnt::PROCESS_QUERY_LIMITED_INFORMATION, 0, pid);
+        if handle.is_null() {
+            return false;
+        }
+        let mut exitCode = 0;
+        let result = winapi::um::processthreadsapi::GetExitCodeProcess(handle, &mut exitCode);
+        winapi::um::handleapi::CloseHandle(handle);
+        result != 0 && exitCode == winapi::um::minwinbase::STILL_ACTIVE
+    }
+}
+
 /// 字符串转时间
 pub fn StringToSystemTime(time: &str) -> Result<SystemTime> {
     let custom = NaiveDateTime::parse_from_str(time, "%Y-%m-%d %H:%M:%S")?;
@@ -0,0 +1,168 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Result};
+
+/// 分卷压缩包(由多个文件组成的压缩包)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VolumeSet {
+    /// 压缩包名称(去除分卷编号，如 Game.7z.001 为 Game.7z)
+    pub name: String,
+    /// 打开压缩包使用的分卷(7-Zip 由该分卷读取其余分卷)
+    pub primaryPath: PathBuf,
+    /// 所有分卷(按顺序，非分卷压缩包仅包含其本身)
+    pub volumeList: Vec<PathBuf>,
+}
+
+/// 分卷文件名: 前缀 + 编号(补零至指定宽度) + 后缀
+/// - Game.7z.001、Game.zip.001: 前缀 `Game.7z.`
+/// - Game.part1.rar: 前缀 `Game.part`，后缀 `.rar`
+/// - Game.z01 ... Game.zip: 前缀 `Game.z`，最后一卷为 Game.zip
+#[derive(Debug, Clone)]
+struct VolumeName {
+    prefix: String,
+    width: usize,
+    suffix: String,
+    /// 分卷编号(zip 分卷的最后一卷为 None)
+    index: Option<u32>,
+    /// 是否为 zip 分卷(.z01)
+    zipSplit: bool,
+}
+
+impl VolumeName {
+    /// 解析分卷文件名(不检查其他分卷是否存在，Game.zip 不识别为分卷)
+    fn parse(fileName: &str) -> Option<VolumeName> {
+        let (stem, extension) = fileName.rsplit_once('.')?;
+        let isNumber = |text: &str| !text.is_empty() && text.chars().all(|c| c.is_ascii_digit());
+        // Game.7z.001
+        if extension.len() >= 3 && isNumber(extension) {
+            return Some(VolumeName { prefix: format!("{}.", stem), width: extension.len(), suffix: String::new(), index: extension.parse().ok(), zipSplit: false });
+        }
+        // Game.part1.rar
+        if extension.eq_ignore_ascii_case("rar") {
+            let (base, part) = stem.rsplit_once('.')?;
+            if part.get(..4).map(|text| text.eq_ignore_ascii_case("part")).unwrap_or(false) && isNumber(&part[4..]) {
+                return Some(VolumeName { prefix: format!("{}.{}", base, &part[..4]), width: part.len() - 4, suffix: format!(".{}", extension), index: part[4..].parse().ok(), zipSplit: false });
+            }
+            return None;
+        }
+        // Game.z01
+        if extension.len() >= 3 && extension.get(..1).map(|text| text.eq_ignore_ascii_case("z")).unwrap_or(false) && isNumber(&extension[1..]) {
+            return Some(VolumeName { prefix: format!("{}.{}", stem, &extension[..1]), width: extension.len() - 1, suffix: String::new(), index: extension[1..].parse().ok(), zipSplit: true });
+        }
+        None
+    }
+
+    /// 第 index 卷的文件名
+    fn getFileName(&self, index: u32) -> String {
+        format!("{}{:0width$}{}", self.prefix, index, self.suffix, width = self.width)
+    }
+
+    /// zip 分卷的最后一卷(Game.zip)
+    fn getZipFileName(&self) -> String {
+        format!("{}ip", self.prefix)
+    }
+
+    /// 是否为打开压缩包使用的分卷(zip 分卷为最后一卷，其他为第一卷)
+    fn isPrimary(&self) -> bool {
+        if self.zipSplit { self.index.is_none() } else { self.index == Some(1) }
+    }
+
+    /// 压缩包名称
+    fn getSetName(&self) -> String {
+        if self.zipSplit {
+            self.getZipFileName()
+        } else if self.suffix.is_empty() {
+            self.prefix.trim_end_matches('.').to_string()
+        } else {
+            // Game.part -> Game.rar
+            format!("{}{}", &self.prefix[..self.prefix.len() - 5], self.suffix)
+        }
+    }
+
+    /// 文件名是否属于该分卷压缩包，返回分卷编号(zip 分卷的最后一卷为 0)
+    fn getIndex(&self, fileName: &str) -> Option<u32> {
+        if self.zipSplit && fileName.eq_ignore_ascii_case(&self.getZipFileName()) {
+            return Some(0);
+        }
+        let lowerName = fileName.to_lowercase();
+        let number = lowerName.strip_prefix(&self.prefix.to_lowercase())?.strip_suffix(&self.suffix.to_lowercase())?;
+        if number.len() == self.width && number.chars().all(|c| c.is_ascii_digit()) { number.parse().ok() } else { None }
+    }
+}
+
+/// 识别压缩包所属的分卷压缩包(可指定任意分卷)，缺少分卷时返回错误并列出缺少的分卷
+pub fn resolveVolumes(archivePath: &Path) -> Result<VolumeSet> {
+    let fileName = archivePath.file_name().and_then(|name| name.to_str()).unwrap_or("");
+    let parentPath = archivePath.parent().unwrap_or_else(|| Path::new(""));
+    let single = || VolumeSet { name: fileName.to_string(), primaryPath: archivePath.to_path_buf(), volumeList: vec![archivePath.to_path_buf()] };
+    let siblingList: Vec<String> = match parentPath.read_dir() {
+        Ok(entries) => entries.flatten().filter_map(|entry| entry.file_name().to_str().map(|name| name.to_string())).collect(),
+        Err(_) => return Ok(single()),
+    };
+    let volumeName = match VolumeName::parse(fileName) {
+        Some(volumeName) => volumeName,
+        None => {
+            // Game.zip 存在 Game.z01 时为 zip 分卷的最后一卷
+            let stem = if fileName.to_lowercase().ends_with(".zip") { Some(&fileName[..fileName.len() - 4]) } else { None };
+            match stem.and_then(|stem| siblingList.iter().filter_map(|name| VolumeName::parse(name)).find(|item| item.zipSplit && item.prefix[..item.prefix.len() - 1].eq_ignore_ascii_case(&format!("{}.", stem)))) {
+                Some(volumeName) => volumeName,
+                None => return Ok(single()),
+            }
+        }
+    };
+
+    // 编号连续的分卷(zip 分卷另需最后一卷)
+    let indexList: Vec<u32> = siblingList.iter().filter_map(|name| volumeName.getIndex(name)).filter(|&index| index > 0).collect();
+    let lastIndex = indexList.iter().copied().max().unwrap_or(0).max(volumeName.index.unwrap_or(0));
+    let mut volumeList = Vec::new();
+    let mut missingList = Vec::new();
+    for index in 1..=lastIndex {
+        let name = siblingList.iter().find(|name| volumeName.getIndex(name) == Some(index)).cloned();
+        match name {
+            Some(name) => volumeList.push(parentPath.join(name)),
+            None => missingList.push(volumeName.getFileName(index)),
+        }
+    }
+    let mut primaryPath = volumeList.first().cloned().unwrap_or_else(|| archivePath.to_path_buf());
+    if volumeName.zipSplit {
+        match siblingList.iter().find(|name| volumeName.getIndex(name) == Some(0)) {
+            Some(name) => {
+                primaryPath = parentPath.join(name);
+                volumeList.push(primaryPath.clone());
+            }
+            None => missingList.push(volumeName.getZipFileName()),
+        }
+    }
+    if !missingList.is_empty() {
+        bail!("The archive volumes are missing: {}", missingList.join(", "));
+    }
+    Ok(VolumeSet { name: volumeName.getSetName(), primaryPath, volumeList })
+}
+
+/// 检查分卷是否完整(缺少末尾的分卷时编号仍连续，需由 7-Zip 列出压缩包的输出判断)
+/// 参数1: 7-Zip 列出压缩包的输出
+/// 参数2: 找到的分卷
+pub fn checkVolumes(archiveInfo: &str, volumeList: &[PathBuf]) -> Result<()> {
+    let missingList: Vec<&str> = archiveInfo.lines()
+        .filter_map(|line| line.split_once("Missing volume"))
+        .map(|(_, name)| name.trim_start_matches(|c| c == ' ' || c == ':').trim())
+        .filter(|name| !name.is_empty())
+        .collect();
+    if !missingList.is_empty() {
+        bail!("The archive volumes are missing: {}", missingList.join(", "));
+    }
+    let volumeCount = archiveInfo.lines().filter_map(|line| line.trim().strip_prefix("Volumes = ")).filter_map(|count| count.trim().parse::<usize>().ok()).max();
+    if let Some(volumeCount) = volumeCount.filter(|&volumeCount| volumeCount > volumeList.len()) {
+        bail!("The archive volumes are missing: {} of {} volumes found", volumeList.len(), volumeCount);
+    }
+    if archiveInfo.contains("Unexpected end of archive") {
+        let lastName = volumeList.last().and_then(|path| path.file_name()).map(|name| name.to_string_lossy().to_string()).unwrap_or_default();
+        bail!("The archive volumes are missing: volumes after {}", lastName);
+    }
+    Ok(())
+}
+
+/// 按文件名判断是否为分卷(不检查其他分卷)，返回压缩包名称及是否为打开压缩包使用的分卷
+pub fn parseVolumeFileName(fileName: &str) -> Option<(String, bool)> {
+    VolumeName::parse(fileName).map(|volumeName| (volumeName.getSetName(), volumeName.isPrimary()))
+}
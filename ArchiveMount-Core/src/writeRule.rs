@@ -0,0 +1,110 @@
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+
+use anyhow::{anyhow, bail, Result};
+use globset::{GlobBuilder, GlobMatcher};
+
+/// 写入策略
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WritePolicy {
+    /// 写入配置目录(卸载后保留)
+    Persist,
+    /// 写入临时目录(卸载后删除)
+    Discard,
+    /// 禁止写入
+    Deny,
+}
+
+impl FromStr for WritePolicy {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "persist" => Ok(WritePolicy::Persist),
+            "discard" => Ok(WritePolicy::Discard),
+            "deny" => Ok(WritePolicy::Deny),
+            _ => Err(anyhow!("Unknown write policy: {}", s)),
+        }
+    }
+}
+
+/// 路径匹配模式(glob)
+/// - 不区分大小写，`\\` 与 `/` 均视为目录分隔符
+/// - 不含 / 时匹配任意目录下的文件名
+#[derive(Debug, Clone)]
+pub struct PathPattern {
+    matcher: GlobMatcher,
+    matchFileName: bool,
+}
+
+impl PathPattern {
+    pub fn new(pattern: &str) -> Result<PathPattern> {
+        let pattern = pattern.replace('\\', "/");
+        let pattern = pattern.trim_start_matches('/');
+        let glob = GlobBuilder::new(pattern)
+            .case_insensitive(true)
+            .literal_separator(true)
+            .build()?;
+        Ok(PathPattern { matcher: glob.compile_matcher(), matchFileName: !pattern.contains('/') })
+    }
+
+    /// 是否匹配压缩包内的相对路径
+    pub fn isMatch(&self, relativePath: &str) -> bool {
+        let path = relativePath.replace('\\', "/");
+        let path = path.trim_matches('/');
+        if self.matchFileName {
+            self.matcher.is_match(path.rsplit('/').next().unwrap_or(path))
+        } else {
+            self.matcher.is_match(path)
+        }
+    }
+}
+
+/// 写入规则
+/// 规则文件每行一条规则: `策略 匹配模式`，如 `persist saves/**`、`discard *.log`、`deny *.exe`
+/// - 以 # 开头的行为注释
+/// - 匹配模式不区分大小写，不含 / 时匹配任意目录下的文件名
+/// - 按顺序匹配，首条匹配的规则生效，均未匹配时写入默认覆盖层
+#[derive(Debug, Default)]
+pub struct WriteRules {
+    ruleList: Vec<(PathPattern, WritePolicy)>,
+}
+
+impl WriteRules {
+    /// 读取规则文件
+    pub fn load(ruleFilePath: &Path) -> Result<WriteRules> {
+        WriteRules::parse(&fs::read_to_string(ruleFilePath)?)
+    }
+
+    /// 解析规则
+    pub fn parse(content: &str) -> Result<WriteRules> {
+        let mut ruleList = Vec::new();
+        for (index, line) in content.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (policy, pattern) = match line.split_once(char::is_whitespace) {
+                Some((policy, pattern)) if !pattern.trim().is_empty() => (policy, pattern.trim()),
+                _ => bail!("Invalid rule at line {}: {}", index + 1, line),
+            };
+            let policy = WritePolicy::from_str(policy)?;
+            let pattern = PathPattern::new(pattern).map_err(|err| anyhow!("Invalid pattern at line {}: {}", index + 1, err))?;
+            ruleList.push((pattern, policy));
+        }
+        Ok(WriteRules { ruleList })
+    }
+
+    /// 获取路径的写入策略(未匹配任何规则时返回 None)
+    pub fn getPolicy(&self, relativePath: &str) -> Option<WritePolicy> {
+        self.ruleList.iter()
+            .find(|(pattern, _)| pattern.isMatch(relativePath))
+            .map(|(_, policy)| *policy)
+    }
+
+    /// 是否包含指定策略的规则
+    pub fn hasPolicy(&self, policy: WritePolicy) -> bool {
+        self.ruleList.iter().any(|(_, item)| *item == policy)
+    }
+}
@@ -0,0 +1,6 @@
+@echo off
+cd /d %~dp0
+
+cargo build --release
+
+start "" "%cd%\target\release"
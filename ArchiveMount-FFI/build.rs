@@ -0,0 +1,14 @@
+// 禁用变量命名警告
+#![allow(non_snake_case)]
+
+use std::env;
+
+// 生成 C 头文件
+fn main() {
+    let crateDir = env::var("CARGO_MANIFEST_DIR").unwrap();
+    cbindgen::generate(&crateDir)
+        .expect("Unable to generate C header")
+        .write_to_file("include/ArchiveMount.h");
+    println!("cargo:rerun-if-changed=src/lib.rs");
+    println!("cargo:rerun-if-changed=cbindgen.toml");
+}
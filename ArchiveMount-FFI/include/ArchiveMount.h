@@ -0,0 +1,221 @@
+/* ArchiveMount C API, generated by cbindgen, do not edit */
+
+#ifndef ARCHIVE_MOUNT_H
+#define ARCHIVE_MOUNT_H
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+/**
+ * 成功
+ */
+#define AM_OK 0
+
+/**
+ * 一般错误
+ */
+#define AM_ERROR 1
+
+/**
+ * 参数无效(空指针、非 UTF-8 字符串、索引越界)
+ */
+#define AM_INVALID_ARGUMENT 2
+
+/**
+ * 打开的压缩包(只读访问条目)
+ */
+typedef struct AmArchive AmArchive;
+
+/**
+ * 挂载
+ */
+typedef struct AmMount AmMount;
+
+/**
+ * 压缩包条目
+ */
+typedef struct AmEntry {
+  /**
+   * 条目路径(压缩包关闭前有效)
+   */
+  const char *path;
+  /**
+   * 文件大小
+   */
+  uint64_t size;
+  /**
+   * 文件大小(压缩后)
+   */
+  uint64_t packedSize;
+  /**
+   * 是否为目录
+   */
+  bool isDir;
+} AmEntry;
+
+/**
+ * 事件回调
+ * 参数1: JSON 事件(与 `ArchiveMount.exe --output json` 的输出一致，仅在回调期间有效)
+ * 参数2: 挂载参数中的 userData
+ */
+typedef void (*AmEventCallback)(const char *event, void *userData);
+
+/**
+ * 挂载参数
+ */
+typedef struct AmMountOptions {
+  /**
+   * 压缩包路径
+   */
+  const char *archivePath;
+  /**
+   * 挂载路径(盘符或空目录)
+   */
+  const char *mountPath;
+  /**
+   * 压缩包密码(可为空)
+   */
+  const char *password;
+  /**
+   * 缓存目录(可为空，默认临时目录)
+   */
+  const char *tempPath;
+  /**
+   * 持久覆盖层目录(可为空，默认卸载后删除写入的文件)
+   */
+  const char *overlayPath;
+  /**
+   * 7-Zip 程序路径(可为空，默认内置 7z.exe)
+   */
+  const char *sevenZipPath;
+  /**
+   * 缓存大小(单位: MB，0为默认值)
+   */
+  uint64_t cacheSize;
+  /**
+   * 线程数(0为自动)
+   */
+  uint16_t threadCount;
+  /**
+   * 是否只读挂载
+   */
+  bool readOnly;
+  /**
+   * 事件回调(可为空，为空时输出至控制台)
+   */
+  AmEventCallback onEvent;
+  /**
+   * 传递给事件回调的用户数据
+   */
+  void *userData;
+} AmMountOptions;
+
+/**
+ * 挂载运行统计
+ */
+typedef struct AmStats {
+  /**
+   * 是否仍处于挂载状态
+   */
+  bool mounted;
+  /**
+   * 已挂载时长(单位: 秒)
+   */
+  uint64_t uptime;
+  /**
+   * 缓存占用大小(单位: 字节)
+   */
+  uint64_t cacheUsage;
+  /**
+   * 打开的句柄数
+   */
+  uint64_t openHandles;
+  /**
+   * 最后活动时间(UNIX时间戳，单位: 秒)
+   */
+  uint64_t lastActivity;
+  /**
+   * 读取字节数
+   */
+  uint64_t readBytes;
+  /**
+   * 写入字节数
+   */
+  uint64_t writeBytes;
+} AmStats;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/**
+ * 打开压缩包并读取条目列表
+ * 参数2: 压缩包密码(可为空)
+ * 参数3: 返回压缩包，使用后由 amCloseArchive 关闭
+ */
+int32_t amOpenArchive(const char *archivePath, const char *password, struct AmArchive **archive);
+
+/**
+ * 关闭压缩包(删除读取条目时的缓存)
+ */
+void amCloseArchive(struct AmArchive *archive);
+
+/**
+ * 条目数量
+ */
+size_t amGetEntryCount(const struct AmArchive *archive);
+
+/**
+ * 获取条目信息
+ */
+int32_t amGetEntry(const struct AmArchive *archive, size_t index, struct AmEntry *entry);
+
+/**
+ * 读取条目内容(首次读取时解压至缓存目录)
+ * 参数2: 条目路径(与 amGetEntry 返回的路径一致，不区分大小写)
+ * 参数3: 读取位置
+ * 参数4: 缓冲区
+ * 参数5: 缓冲区大小
+ * 参数6: 返回实际读取的字节数(到达文件末尾时小于缓冲区大小)
+ */
+int32_t amReadEntry(struct AmArchive *archive,
+                    const char *path,
+                    uint64_t offset,
+                    uint8_t *buffer,
+                    size_t length,
+                    size_t *bytesRead);
+
+/**
+ * 挂载压缩包，挂载完成或失败后返回
+ * 参数2: 返回挂载，由 amUnmount 卸载并释放
+ */
+int32_t amMount(const struct AmMountOptions *options, struct AmMount **mount);
+
+/**
+ * 卸载并释放挂载(卸载失败时挂载仍会被释放)
+ */
+int32_t amUnmount(struct AmMount *mount);
+
+/**
+ * 获取挂载运行统计
+ */
+int32_t amGetStats(const struct AmMount *mount, struct AmStats *stats);
+
+/**
+ * 当前线程最后一次错误的详细信息(下次调用接口前有效)
+ */
+const char *amGetLastError(void);
+
+/**
+ * 状态码对应的错误信息(静态字符串，未知状态码返回空指针)
+ */
+const char *amGetErrorString(int32_t code);
+
+#ifdef __cplusplus
+} // extern "C"
+#endif // __cplusplus
+
+#endif /* ARCHIVE_MOUNT_H */
@@ -0,0 +1,412 @@
+//! ArchiveMount C 接口: 供 C/C++ 程序在进程内读取压缩包及挂载
+//!
+//! - 返回 `int32_t` 的函数返回状态码，`AM_OK` 为成功，其他值的详细信息由 `amGetLastError` 获取
+//! - 传入的字符串均为 UTF-8 编码，可为空指针的参数已在注释中说明
+//! - 头文件 `include/ArchiveMount.h` 由 cbindgen 在构建时生成
+
+// 禁用变量命名警告
+#![allow(non_snake_case)]
+#![allow(non_camel_case_types)]
+#![allow(clippy::missing_safety_doc)]
+
+#[macro_use]
+extern crate lazy_static;
+
+use std::cell::RefCell;
+use std::ffi::{CStr, CString};
+use std::fs;
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::os::raw::{c_char, c_void};
+use std::path::PathBuf;
+use std::process;
+use std::ptr;
+use std::slice;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use anyhow::{anyhow, bail, Result};
+use ArchiveMount::{MountBuilder, MountFailed, MountHandle, TEMP_PATH};
+use ArchiveMount::mountOptions::getMountErrorCodeMessage;
+use ArchiveMount::sevenZip::{ArchiveFileInfo, sevenZip};
+
+/// 成功
+pub const AM_OK: i32 = 0;
+/// 一般错误
+pub const AM_ERROR: i32 = 1;
+/// 参数无效(空指针、非 UTF-8 字符串、索引越界)
+pub const AM_INVALID_ARGUMENT: i32 = 2;
+// 10 ~ 16 为 Dokan 挂载错误，与 ArchiveMount.exe 的退出码一致
+
+/// 打开的压缩包序号(用于区分缓存目录)
+static ARCHIVE_INDEX: AtomicUsize = AtomicUsize::new(0);
+
+thread_local! {
+    /// 当前线程最后一次错误信息
+    static LAST_ERROR: RefCell<CString> = RefCell::new(CString::default());
+}
+
+lazy_static! {
+    /// 状态码对应的错误信息
+    static ref ERROR_STRINGS: Vec<(i32, CString)> = [AM_OK, AM_ERROR, AM_INVALID_ARGUMENT].into_iter().chain(10..=16)
+        .map(|code| {
+            let message = match code {
+                AM_OK => "Success",
+                AM_ERROR => "An error occurred",
+                AM_INVALID_ARGUMENT => "Invalid argument",
+                code => getMountErrorCodeMessage(code).unwrap_or("Unknown error"),
+            };
+            (code, CString::new(message).unwrap())
+        })
+        .collect();
+}
+
+/// 打开的压缩包(只读访问条目)
+pub struct AmArchive {
+    sevenZip: sevenZip,
+    archivePath: PathBuf,
+    password: Option<String>,
+    /// 缓存目录(读取条目时解压)
+    extractPath: PathBuf,
+    entryList: Vec<ArchiveFileInfo>,
+    /// 条目路径(C 字符串，压缩包关闭前有效)
+    pathList: Vec<CString>,
+}
+
+/// 压缩包条目
+#[repr(C)]
+pub struct AmEntry {
+    /// 条目路径(压缩包关闭前有效)
+    pub path: *const c_char,
+    /// 文件大小
+    pub size: u64,
+    /// 文件大小(压缩后)
+    pub packedSize: u64,
+    /// 是否为目录
+    pub isDir: bool,
+}
+
+/// 事件回调
+/// 参数1: JSON 事件(与 `ArchiveMount.exe --output json` 的输出一致，仅在回调期间有效)
+/// 参数2: 挂载参数中的 userData
+pub type AmEventCallback = Option<extern "C" fn(event: *const c_char, userData: *mut c_void)>;
+
+/// 挂载参数
+#[repr(C)]
+pub struct AmMountOptions {
+    /// 压缩包路径
+    pub archivePath: *const c_char,
+    /// 挂载路径(盘符或空目录)
+    pub mountPath: *const c_char,
+    /// 压缩包密码(可为空)
+    pub password: *const c_char,
+    /// 缓存目录(可为空，默认临时目录)
+    pub tempPath: *const c_char,
+    /// 持久覆盖层目录(可为空，默认卸载后删除写入的文件)
+    pub overlayPath: *const c_char,
+    /// 7-Zip 程序路径(可为空，默认内置 7z.exe)
+    pub sevenZipPath: *const c_char,
+    /// 缓存大小(单位: MB，0为默认值)
+    pub cacheSize: u64,
+    /// 线程数(0为自动)
+    pub threadCount: u16,
+    /// 是否只读挂载
+    pub readOnly: bool,
+    /// 事件回调(可为空，为空时输出至控制台)
+    pub onEvent: AmEventCallback,
+    /// 传递给事件回调的用户数据
+    pub userData: *mut c_void,
+}
+
+/// 挂载运行统计
+#[repr(C)]
+pub struct AmStats {
+    /// 是否仍处于挂载状态
+    pub mounted: bool,
+    /// 已挂载时长(单位: 秒)
+    pub uptime: u64,
+    /// 缓存占用大小(单位: 字节)
+    pub cacheUsage: u64,
+    /// 打开的句柄数
+    pub openHandles: u64,
+    /// 最后活动时间(UNIX时间戳，单位: 秒)
+    pub lastActivity: u64,
+    /// 读取字节数
+    pub readBytes: u64,
+    /// 写入字节数
+    pub writeBytes: u64,
+}
+
+/// 挂载
+pub struct AmMount {
+    handle: MountHandle,
+}
+
+/// 事件回调及用户数据(由调用方保证 userData 可跨线程使用)
+struct EventCallback {
+    callback: extern "C" fn(*const c_char, *mut c_void),
+    userData: *mut c_void,
+}
+
+impl EventCallback {
+    fn call(&self, event: &CStr) {
+        (self.callback)(event.as_ptr(), self.userData);
+    }
+}
+
+unsafe impl Send for EventCallback {}
+
+unsafe impl Sync for EventCallback {}
+
+/// 记录错误信息并返回状态码
+fn setLastError(code: i32, message: &str) -> i32 {
+    let message = CString::new(message.replace('\0', "")).unwrap_or_default();
+    LAST_ERROR.with(|lastError| *lastError.borrow_mut() = message);
+    code
+}
+
+/// 记录错误信息并返回状态码(挂载失败时为对应的退出码)
+fn handleError(err: anyhow::Error) -> i32 {
+    let code = err.downcast_ref::<MountFailed>().map(|mountFailed| mountFailed.code()).unwrap_or(AM_ERROR);
+    setLastError(code, &err.to_string())
+}
+
+/// C 字符串转为 Rust 字符串(空指针返回 None)
+unsafe fn toString(value: *const c_char) -> Result<Option<String>> {
+    if value.is_null() {
+        return Ok(None);
+    }
+    match CStr::from_ptr(value).to_str() {
+        Ok(value) => Ok(Some(value.to_string())),
+        Err(_) => bail!("The string is not valid UTF-8"),
+    }
+}
+
+/// 条目路径是否仅由普通的目录名、文件名组成(不含 .、..、盘符及根目录)
+fn isRelativeEntryPath(path: &str) -> bool {
+    path.split(|c| c == '\\' || c == '/').all(|name| !name.is_empty() && name != "." && name != ".." && !name.contains(':'))
+}
+
+/// C 字符串转为路径(不可为空)
+unsafe fn toPath(value: *const c_char) -> Result<PathBuf> {
+    toString(value)?.map(PathBuf::from).ok_or_else(|| anyhow!("The path is null"))
+}
+
+/// 打开压缩包并读取条目列表
+/// 参数2: 压缩包密码(可为空)
+/// 参数3: 返回压缩包，使用后由 amCloseArchive 关闭
+#[no_mangle]
+pub unsafe extern "C" fn amOpenArchive(archivePath: *const c_char, password: *const c_char, archive: *mut *mut AmArchive) -> i32 {
+    if archive.is_null() {
+        return setLastError(AM_INVALID_ARGUMENT, "The archive pointer is null");
+    }
+    let (archivePath, password) = match (toPath(archivePath), toString(password)) {
+        (Ok(archivePath), Ok(password)) => (archivePath, password),
+        (Err(err), _) | (_, Err(err)) => return setLastError(AM_INVALID_ARGUMENT, &err.to_string()),
+    };
+    let result = (|| -> Result<AmArchive> {
+        let sevenZip = sevenZip::new()?;
+        let entryList = sevenZip.listArchiveFiles(&archivePath, password.as_deref())?;
+        // 无法识别的压缩包同样没有条目，有效的空压缩包可识别出格式
+        if entryList.is_empty() && sevenZip.getArchiveType(&archivePath, password.as_deref())?.is_empty() {
+            bail!("The Archive information is not detected, please confirm it is the correct archive or encrypted archive");
+        }
+        let pathList = entryList.iter().map(|entry| CString::new(entry.path()).unwrap_or_default()).collect();
+        let extractPath = TEMP_PATH.join("ArchiveTemp").join(format!("FFI-{}-{}", process::id(), ARCHIVE_INDEX.fetch_add(1, Ordering::SeqCst)));
+        Ok(AmArchive { sevenZip, archivePath, password, extractPath, entryList, pathList })
+    })();
+    match result {
+        Ok(value) => {
+            *archive = Box::into_raw(Box::new(value));
+            AM_OK
+        }
+        Err(err) => handleError(err),
+    }
+}
+
+/// 关闭压缩包(删除读取条目时的缓存)
+#[no_mangle]
+pub unsafe extern "C" fn amCloseArchive(archive: *mut AmArchive) {
+    if archive.is_null() {
+        return;
+    }
+    let archive = Box::from_raw(archive);
+    fs::remove_dir_all(&archive.extractPath).ok();
+}
+
+/// 条目数量
+#[no_mangle]
+pub unsafe extern "C" fn amGetEntryCount(archive: *const AmArchive) -> usize {
+    archive.as_ref().map(|archive| archive.entryList.len()).unwrap_or(0)
+}
+
+/// 获取条目信息
+#[no_mangle]
+pub unsafe extern "C" fn amGetEntry(archive: *const AmArchive, index: usize, entry: *mut AmEntry) -> i32 {
+    let archive = match archive.as_ref() {
+        Some(archive) if !entry.is_null() && index < archive.entryList.len() => archive,
+        _ => return setLastError(AM_INVALID_ARGUMENT, "The archive or entry is invalid"),
+    };
+    let info = &archive.entryList[index];
+    *entry = AmEntry { path: archive.pathList[index].as_ptr(), size: info.size(), packedSize: info.packedSize(), isDir: info.isDir() };
+    AM_OK
+}
+
+/// 读取条目内容(首次读取时解压至缓存目录)
+/// 参数2: 条目路径(与 amGetEntry 返回的路径一致，不区分大小写)
+/// 参数3: 读取位置
+/// 参数4: 缓冲区
+/// 参数5: 缓冲区大小
+/// 参数6: 返回实际读取的字节数(到达文件末尾时小于缓冲区大小)
+#[no_mangle]
+pub unsafe extern "C" fn amReadEntry(archive: *mut AmArchive, path: *const c_char, offset: u64, buffer: *mut u8, length: usize, bytesRead: *mut usize) -> i32 {
+    let archive = match archive.as_mut() {
+        Some(archive) if !buffer.is_null() && !bytesRead.is_null() => archive,
+        _ => return setLastError(AM_INVALID_ARGUMENT, "The archive or buffer is null"),
+    };
+    let path = match toString(path) {
+        Ok(Some(path)) => path.replace('/', "\\"),
+        _ => return setLastError(AM_INVALID_ARGUMENT, "The entry path is invalid"),
+    };
+    let buffer = slice::from_raw_parts_mut(buffer, length);
+    let result = (|| -> Result<usize> {
+        let entry = match archive.entryList.iter().find(|entry| entry.path().eq_ignore_ascii_case(&path)) {
+            Some(entry) if !entry.isDir() => entry,
+            Some(_) => bail!("The entry is a directory: {}", path),
+            None => bail!("The entry does not exist: {}", path),
+        };
+        // 条目名包含 .. 或绝对路径时会读取缓存目录之外的文件
+        if !isRelativeEntryPath(entry.path()) {
+            bail!("The entry path is not a relative path: {}", entry.path());
+        }
+        let localPath = archive.extractPath.join(entry.path());
+        if !localPath.exists() {
+            fs::create_dir_all(&archive.extractPath)?;
+            archive.sevenZip.extractFilesFromPath(&archive.archivePath, archive.password.as_deref(), entry.path(), &archive.extractPath)?;
+            if !localPath.exists() {
+                bail!("Extract file failed: {}", path);
+            }
+        }
+        let mut file = File::open(&localPath)?;
+        file.seek(SeekFrom::Start(offset))?;
+        // 读取至缓冲区满或文件末尾
+        let mut total = 0;
+        while total < buffer.len() {
+            let size = file.read(&mut buffer[total..])?;
+            if size == 0 {
+                break;
+            }
+            total += size;
+        }
+        Ok(total)
+    })();
+    match result {
+        Ok(total) => {
+            *bytesRead = total;
+            AM_OK
+        }
+        Err(err) => handleError(err),
+    }
+}
+
+/// 挂载压缩包，挂载完成或失败后返回
+/// 参数2: 返回挂载，由 amUnmount 卸载并释放
+#[no_mangle]
+pub unsafe extern "C" fn amMount(options: *const AmMountOptions, mount: *mut *mut AmMount) -> i32 {
+    let options = match options.as_ref() {
+        Some(options) if !mount.is_null() => options,
+        _ => return setLastError(AM_INVALID_ARGUMENT, "The options or mount pointer is null"),
+    };
+    // 参数为空指针或不是有效的 UTF-8 时返回参数错误
+    let strings = (|| -> Result<_> {
+        Ok((
+            toPath(options.archivePath)?,
+            toPath(options.mountPath)?,
+            toString(options.password)?,
+            toString(options.tempPath)?,
+            toString(options.overlayPath)?,
+            toString(options.sevenZipPath)?,
+        ))
+    })();
+    let (archivePath, mountPath, password, tempPath, overlayPath, sevenZipPath) = match strings {
+        Ok(strings) => strings,
+        Err(err) => return setLastError(AM_INVALID_ARGUMENT, &err.to_string()),
+    };
+    let mut builder = MountBuilder::new(archivePath, mountPath)
+        .threadCount(options.threadCount)
+        .readOnly(options.readOnly);
+    if let Some(password) = password {
+        builder = builder.password(&password);
+    }
+    if let Some(tempPath) = tempPath {
+        builder = builder.tempPath(tempPath);
+    }
+    if let Some(overlayPath) = overlayPath {
+        builder = builder.overlay(overlayPath);
+    }
+    if let Some(sevenZipPath) = sevenZipPath {
+        builder = builder.sevenZip(sevenZipPath);
+    }
+    if options.cacheSize != 0 {
+        builder = builder.cacheSize(options.cacheSize);
+    }
+    if let Some(callback) = options.onEvent {
+        let eventCallback = EventCallback { callback, userData: options.userData };
+        builder = builder.onEvent(move |event| {
+            if let Ok(event) = serde_json::to_string(event).map(|event| CString::new(event).unwrap_or_default()) {
+                eventCallback.call(&event);
+            }
+        });
+    }
+    match builder.mount() {
+        Ok(handle) => {
+            *mount = Box::into_raw(Box::new(AmMount { handle }));
+            AM_OK
+        }
+        Err(err) => handleError(err),
+    }
+}
+
+/// 卸载并释放挂载(卸载失败时挂载仍会被释放)
+#[no_mangle]
+pub unsafe extern "C" fn amUnmount(mount: *mut AmMount) -> i32 {
+    if mount.is_null() {
+        return setLastError(AM_INVALID_ARGUMENT, "The mount is null");
+    }
+    let mount = Box::from_raw(mount);
+    match mount.handle.unmount() {
+        Ok(()) => AM_OK,
+        Err(err) => handleError(err),
+    }
+}
+
+/// 获取挂载运行统计
+#[no_mangle]
+pub unsafe extern "C" fn amGetStats(mount: *const AmMount, stats: *mut AmStats) -> i32 {
+    let mount = match mount.as_ref() {
+        Some(mount) if !stats.is_null() => mount,
+        _ => return setLastError(AM_INVALID_ARGUMENT, "The mount or stats pointer is null"),
+    };
+    let status = mount.handle.stats();
+    *stats = AmStats {
+        mounted: mount.handle.isMounted(),
+        uptime: status.uptime,
+        cacheUsage: status.cacheUsage,
+        openHandles: status.openHandles.unwrap_or(0) as u64,
+        lastActivity: status.lastActivity.unwrap_or(0),
+        readBytes: status.readBytes.unwrap_or(0),
+        writeBytes: status.writeBytes.unwrap_or(0),
+    };
+    AM_OK
+}
+
+/// 当前线程最后一次错误的详细信息(下次调用接口前有效)
+#[no_mangle]
+pub extern "C" fn amGetLastError() -> *const c_char {
+    LAST_ERROR.with(|lastError| lastError.borrow().as_ptr())
+}
+
+/// 状态码对应的错误信息(静态字符串，未知状态码返回空指针)
+#[no_mangle]
+pub extern "C" fn amGetErrorString(code: i32) -> *const c_char {
+    ERROR_STRINGS.iter().find(|(item, _)| *item == code).map(|(_, message)| message.as_ptr()).unwrap_or(ptr::null())
+}